@@ -0,0 +1,738 @@
+//! Deriving reflection for enums.
+//!
+//! Enums with unit variants only are represented as string primitives. Enums with data are
+//! represented as objects, either externally tagged (`{"variant": {...}}`) or, with the
+//! `#[dynser(tag = "...")]` option, internally tagged (`{"tag": "variant", ...}`). With the
+//! `#[dynser(untagged)]` option, enums are untagged unions, with variant selected by the value.
+//! Variants carrying data must be newtype variants.
+use crate::{wrapped_type, ContainerOptions, FieldOptions, FieldShape, Reflection, ShapeKind};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::parse::Error;
+use syn::spanned::Spanned;
+use syn::{DataEnum, Field, Fields, Ident, LitStr};
+
+struct Variant<'a> {
+    ident: &'a Ident,
+    name: String,
+    aliases: Vec<LitStr>,
+    /// Field of the newtype variant, `None` for unit variants.
+    field: Option<(&'a Field, FieldShape)>,
+}
+
+pub fn derive_enum(
+    s: &synstructure::Structure,
+    de: &DataEnum,
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let mut variants = Vec::new();
+    let mut all_names = Vec::new();
+    for variant in de.variants.iter() {
+        let variant_options = FieldOptions::parse(&variant.attrs)?;
+        let name = match (&variant_options.rename, options.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_variant(&variant.ident.to_string()),
+            (None, None) => variant.ident.to_string(),
+        };
+        variant_options.check_unique(&mut all_names, &name, variant.ident.span())?;
+
+        let field = match variant.fields {
+            Fields::Unit => None,
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                let field = fields.unnamed.first().unwrap().into_value();
+                crate::check_marker_attributes(field)?;
+                let shape = FieldShape::parse(&field.ty)?;
+                if shape.optional {
+                    return Err(Error::new(
+                        field.ty.span(),
+                        "`Option` is not supported in enum variants",
+                    ));
+                }
+                Some((field, shape))
+            }
+            _ => {
+                return Err(Error::new(
+                    variant.span(),
+                    "only unit and newtype variants are supported",
+                ))
+            }
+        };
+        variants.push(Variant {
+            ident: &variant.ident,
+            name,
+            aliases: variant_options.aliases,
+            field,
+        });
+    }
+
+    let is_unit = variants.iter().all(|variant| variant.field.is_none());
+    match (&options.tag, &options.untagged) {
+        (Some(_), Some(untagged)) => Err(Error::new(
+            untagged.span(),
+            "enum cannot be both internally tagged and untagged",
+        )),
+        (None, Some(_)) => derive_untagged(s, &variants, options),
+        (Some(tag), None) => derive_internally_tagged(s, &variants, tag, options),
+        (None, None) if is_unit => Ok(derive_unit_enum(s, &variants, options)),
+        (None, None) => derive_externally_tagged(s, &variants, options),
+    }
+}
+
+/// Unit-only enums are primitives holding the variant name.
+fn derive_unit_enum(
+    s: &synstructure::Structure,
+    variants: &[Variant],
+    options: &ContainerOptions,
+) -> TokenStream {
+    let krate = &options.krate;
+    let name = &s.ast().ident;
+    let variant_names = variant_names(variants);
+    let mut set_body = TokenStream::new();
+    let mut value_body = TokenStream::new();
+    for variant in variants {
+        let ident = variant.ident;
+        let variant_name = &variant.name;
+        let aliases = &variant.aliases;
+        set_body.extend(quote!(#variant_name #(| #aliases)* => #name::#ident,));
+        value_body.extend(quote!(#name::#ident => #variant_name,));
+    }
+
+    let mut tokens = s.gen_impl(quote! {
+        use #krate::reflection::{Primitive, PrimitiveValue, PrimitiveValueKind, ReflectionError};
+
+        gen impl Primitive for @Self {
+            fn kind(&self) -> PrimitiveValueKind {
+                PrimitiveValueKind::String
+            }
+
+            fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError> {
+                match value {
+                    PrimitiveValue::String(value) => {
+                        *self = match value.as_str() {
+                            #set_body
+                            _ => return Err(ReflectionError::UnknownVariant {
+                                value,
+                                expected: #variant_names,
+                            }),
+                        };
+                        Ok(())
+                    }
+                    value => Err(ReflectionError::ValueMismatch {
+                        expected: PrimitiveValueKind::String,
+                        actual: value.kind(),
+                    }),
+                }
+            }
+        }
+    });
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{PrimitiveRef, PrimitiveValueRef};
+
+        gen impl PrimitiveRef for @Self {
+            fn value(&self) -> PrimitiveValueRef {
+                PrimitiveValueRef::String(match *self {
+                    #value_body
+                })
+            }
+        }
+    }));
+    tokens
+}
+
+/// Externally tagged enums are objects with a single field named after the variant.
+fn derive_externally_tagged(
+    s: &synstructure::Structure,
+    variants: &[Variant],
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let krate = &options.krate;
+    let name = &s.ast().ident;
+    let variant_names = variant_names(variants);
+    let mut create_body = TokenStream::new();
+    let mut fields_body = TokenStream::new();
+    for variant in variants {
+        let ident = variant.ident;
+        let variant_name = &variant.name;
+        let aliases = &variant.aliases;
+        let (field, shape) = match variant.field {
+            Some((field, ref shape)) => (field, shape),
+            None => {
+                return Err(Error::new(
+                    ident.span(),
+                    "unit variants are only supported in unit-only or internally tagged enums",
+                ))
+            }
+        };
+        let tag = shape.reflection(field);
+        let ref_tag = tag.read_only();
+        let deref = shape.deref();
+        create_body.extend(quote! {
+            #variant_name #(| #aliases)* => {
+                match *self {
+                    #name::#ident(_) => {}
+                    _ => *self = #name::#ident(Default::default()),
+                }
+                match *self {
+                    #name::#ident(ref mut v) => Ok(FieldMutReflection::#tag(&mut #deref *v)),
+                    _ => unreachable!(),
+                }
+            }
+        });
+        fields_body.extend(quote! {
+            #name::#ident(ref v) => vec![(#variant_name, FieldRefReflection::#ref_tag(&#deref *v))],
+        });
+    }
+
+    let mut tokens = s.gen_impl(quote! {
+        use #krate::reflection::{Object, FieldMutReflection, ReflectionError};
+
+        gen impl Object for @Self {
+            fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+                match field_name {
+                    #create_body
+                    _ => Err(ReflectionError::UnknownVariant {
+                        value: field_name.to_string(),
+                        expected: #variant_names,
+                    }),
+                }
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                #variant_names
+            }
+        }
+    });
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{ObjectRef, FieldRefReflection};
+
+        gen impl ObjectRef for @Self {
+            fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+                match *self {
+                    #fields_body
+                }
+            }
+        }
+    }));
+    Ok(tokens)
+}
+
+/// Internally tagged enums are objects with the variant name stored in the tag field and the rest
+/// of the fields coming from the variant. The tag field is a primitive, backed by the enum itself.
+///
+/// Fields which come before the tag are buffered until the tag selects the variant. If there is no
+/// tag, the tag is reported missing and the fields are skipped.
+fn derive_internally_tagged(
+    s: &synstructure::Structure,
+    variants: &[Variant],
+    tag_name: &LitStr,
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let krate = &options.krate;
+    let name = &s.ast().ident;
+    let variant_names = variant_names(variants);
+    let mut set_body = TokenStream::new();
+    let mut value_body = TokenStream::new();
+    let mut create_body = TokenStream::new();
+    let mut set_null_body = TokenStream::new();
+    let mut discard_body = TokenStream::new();
+    let mut field_names_body = TokenStream::new();
+    let mut required_fields_body = TokenStream::new();
+    let mut aliases_body = TokenStream::new();
+    let mut fields_body = TokenStream::new();
+    for variant in variants {
+        let ident = variant.ident;
+        let variant_name = &variant.name;
+        let aliases = &variant.aliases;
+        value_body.extend(quote!(#name::#ident { .. } => #variant_name,));
+        match variant.field {
+            None => {
+                set_body.extend(quote!(#variant_name #(| #aliases)* => *self = #name::#ident,));
+                create_body.extend(quote! {
+                    #name::#ident => Err(ReflectionError::InvalidField {
+                        name: field_name.to_string(),
+                    }),
+                });
+                set_null_body.extend(quote! {
+                    #name::#ident => Err(ReflectionError::InvalidField {
+                        name: field_name.to_string(),
+                    }),
+                });
+                discard_body.extend(quote!(#name::#ident => {}));
+                field_names_body.extend(quote!(#name::#ident => &[#tag_name],));
+                required_fields_body.extend(quote!(#name::#ident => &[],));
+                aliases_body.extend(quote!(#name::#ident => &[],));
+                fields_body.extend(quote!(#name::#ident => {}));
+            }
+            Some((field, ref shape)) => {
+                match shape.kind {
+                    ShapeKind::Other | ShapeKind::Map => {}
+                    _ => {
+                        return Err(Error::new(
+                            field.ty.span(),
+                            "variants of internally tagged enums must contain objects",
+                        ))
+                    }
+                }
+                let deref = shape.deref();
+                set_body.extend(quote! {
+                    #variant_name #(| #aliases)* => match *self {
+                        #name::#ident(_) => {}
+                        _ => *self = #name::#ident(Default::default()),
+                    },
+                });
+                create_body.extend(quote! {
+                    #name::#ident(ref mut v) => Object::create(&mut #deref *v, field_name),
+                });
+                set_null_body.extend(quote! {
+                    #name::#ident(ref mut v) => Object::set_null(&mut #deref *v, field_name),
+                });
+                discard_body.extend(quote! {
+                    #name::#ident(ref mut v) => Object::discard(&mut #deref *v, field_name),
+                });
+                field_names_body.extend(quote! {
+                    #name::#ident(ref v) => Object::field_names(&#deref *v),
+                });
+                required_fields_body.extend(quote! {
+                    #name::#ident(ref v) => Object::required_fields(&#deref *v),
+                });
+                aliases_body.extend(quote! {
+                    #name::#ident(ref v) => Object::aliases(&#deref *v),
+                });
+                fields_body.extend(quote! {
+                    #name::#ident(ref v) => fields.extend(ObjectRef::fields(&#deref *v)),
+                });
+            }
+        }
+    }
+
+    let mut tokens = s.gen_impl(quote! {
+        use #krate::reflection::{Primitive, PrimitiveValue, PrimitiveValueKind, ReflectionError};
+
+        gen impl Primitive for @Self {
+            fn kind(&self) -> PrimitiveValueKind {
+                PrimitiveValueKind::String
+            }
+
+            fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError> {
+                match value {
+                    PrimitiveValue::String(value) => {
+                        match value.as_str() {
+                            #set_body
+                            _ => return Err(ReflectionError::UnknownVariant {
+                                value,
+                                expected: #variant_names,
+                            }),
+                        }
+                        Ok(())
+                    }
+                    value => Err(ReflectionError::ValueMismatch {
+                        expected: PrimitiveValueKind::String,
+                        actual: value.kind(),
+                    }),
+                }
+            }
+        }
+    });
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{PrimitiveRef, PrimitiveValueRef};
+
+        gen impl PrimitiveRef for @Self {
+            fn value(&self) -> PrimitiveValueRef {
+                PrimitiveValueRef::String(match *self {
+                    #value_body
+                })
+            }
+        }
+    }));
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{Object, FieldMutReflection, ReflectionError};
+
+        gen impl Object for @Self {
+            fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+                if field_name == #tag_name {
+                    return Ok(FieldMutReflection::Primitive(self));
+                }
+                match *self {
+                    #create_body
+                }
+            }
+
+            fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+                if field_name == #tag_name {
+                    return Err(ReflectionError::NotNullable);
+                }
+                match *self {
+                    #set_null_body
+                }
+            }
+
+            fn discard(&mut self, field_name: &str) {
+                if field_name != #tag_name {
+                    match *self {
+                        #discard_body
+                    }
+                }
+            }
+
+            fn select_variant(&mut self, field_names: &[&str]) -> bool {
+                field_names.contains(&#tag_name)
+            }
+
+            fn select_default_variant(
+                &mut self,
+                _field_names: &[&str],
+            ) -> Result<(), ReflectionError> {
+                Err(ReflectionError::MissingField { name: #tag_name })
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                match *self {
+                    #field_names_body
+                }
+            }
+
+            fn required_fields(&self) -> &'static [&'static str] {
+                match *self {
+                    #required_fields_body
+                }
+            }
+
+            fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+                match *self {
+                    #aliases_body
+                }
+            }
+        }
+    }));
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{ObjectRef, FieldRefReflection};
+
+        gen impl ObjectRef for @Self {
+            fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+                let mut fields = vec![(#tag_name, FieldRefReflection::Primitive(self))];
+                match *self {
+                    #fields_body
+                }
+                fields
+            }
+        }
+    }));
+    Ok(tokens)
+}
+
+/// Untagged unions select the variant by the kind of the value. Primitive values go to the first
+/// variant accepting them and lists go to the first list variant. Objects go to the variant
+/// which is the only one knowing the field, buffering fields known to several variants (or none
+/// of them) until such field comes. If none does, the default variant is used (or the first object
+/// variant, if the default one is not an object). Fields are checked against the types of the
+/// variants, so these must implement `ObjectFields` (derived structs, maps and untagged unions do).
+fn derive_untagged(
+    s: &synstructure::Structure,
+    variants: &[Variant],
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let krate = &options.krate;
+    let name = &s.ast().ident;
+
+    let mut object_variants = Vec::new();
+    let mut list_variants = Vec::new();
+    let mut primitive_variants = Vec::new();
+    for variant in variants {
+        let (field, shape) = match variant.field {
+            Some((field, ref shape)) => (field, shape),
+            None => {
+                return Err(Error::new(
+                    variant.ident.span(),
+                    "unit variants are not supported in untagged enums",
+                ))
+            }
+        };
+        let ident = variant.ident;
+        let ty = &field.ty;
+        let deref = shape.deref();
+        let reflection = shape.reflection(field);
+        if reflection == Reflection::Any {
+            return Err(Error::new(
+                ty.span(),
+                "#[any] variants are not supported in untagged enums",
+            ));
+        }
+        if reflection == Reflection::Polymorphic {
+            return Err(Error::new(
+                ty.span(),
+                "`Discriminated` variants are not supported in untagged enums",
+            ));
+        }
+        let variant = quote!(#name::#ident);
+        let entry = (variant, ty, deref);
+        match reflection {
+            Reflection::List => list_variants.push(entry),
+            Reflection::Primitive => primitive_variants.push(entry),
+            _ => object_variants.push(entry),
+        }
+    }
+
+    let object_patterns: Vec<_> = object_variants
+        .iter()
+        .map(|(variant, _, _)| quote!(#variant(_)))
+        .collect();
+    let object_patterns = &object_patterns;
+    let mut create_body = TokenStream::new();
+    let mut set_null_body = TokenStream::new();
+    let mut discard_body = TokenStream::new();
+    let mut field_names_body = TokenStream::new();
+    let mut required_fields_body = TokenStream::new();
+    let mut aliases_body = TokenStream::new();
+    let mut select_variant_body = TokenStream::new();
+    let mut select_variant_arms = TokenStream::new();
+    let mut select_default_body = TokenStream::new();
+    let mut fields_body = TokenStream::new();
+    let mut object_tys = Vec::new();
+    let mut knows_fields_body = quote!(false);
+    let mut missing_fields_body = TokenStream::new();
+    for (idx, (variant, ty, deref)) in object_variants.iter().enumerate() {
+        create_body.extend(quote! {
+            #variant(ref mut v) => Object::create(&mut #deref *v, field_name),
+        });
+        set_null_body.extend(quote! {
+            #variant(ref mut v) => Object::set_null(&mut #deref *v, field_name),
+        });
+        discard_body.extend(quote! {
+            #variant(ref mut v) => Object::discard(&mut #deref *v, field_name),
+        });
+        field_names_body.extend(quote!(#variant(ref v) => Object::field_names(&#deref *v),));
+        required_fields_body
+            .extend(quote!(#variant(ref v) => Object::required_fields(&#deref *v),));
+        aliases_body.extend(quote!(#variant(ref v) => Object::aliases(&#deref *v),));
+        // Fields are checked against the type, so no values are created for that
+        let mut object_ty = *ty;
+        while let Some(inner) = wrapped_type(object_ty, "Box") {
+            object_ty = inner;
+        }
+        let fields = quote_spanned!(ty.span() => <#object_ty as ObjectFields>);
+        object_tys.push(object_ty);
+        knows_fields_body.extend(quote!(|| #fields::knows_fields(field_names)));
+        missing_fields_body.extend(quote! {
+            if #fields::knows_fields(field_names) {
+                let fields = #fields::missing_fields(field_names);
+                if fields.is_empty() {
+                    return fields;
+                } else if missing.is_empty() {
+                    missing = fields;
+                }
+            }
+        });
+        select_variant_body.extend(quote! {
+            if #fields::knows_fields(field_names) {
+                if selected.is_some() {
+                    return false;
+                }
+                selected = Some(#idx);
+            }
+        });
+        select_default_body.extend(quote! {
+            if #fields::knows_fields(field_names) && #fields::missing_fields(field_names).is_empty() {
+                selected = selected.or(Some(#idx));
+            }
+        });
+        select_variant_arms.extend(quote! {
+            Some(#idx) => match *self {
+                #variant(_) => {}
+                _ => *self = #variant(Default::default()),
+            },
+        });
+        fields_body.extend(quote!(#variant(ref v) => ObjectRef::fields(&#deref *v),));
+    }
+    let select_default_variant_body = match object_variants.first() {
+        Some((first, _, _)) => quote! {
+            let mut selected = None;
+            #select_default_body
+            match selected {
+                #select_variant_arms
+                _ => {}
+            }
+            if selected.is_some() {
+                return Ok(());
+            }
+
+            let default = Self::default();
+            match default {
+                #(#object_patterns)|* => {
+                    if std::mem::discriminant(self) != std::mem::discriminant(&default) {
+                        *self = default;
+                    }
+                }
+                _ => match *self {
+                    #(#object_patterns)|* => {}
+                    _ => *self = #first(Default::default()),
+                },
+            }
+            Ok(())
+        },
+        None => quote!(Ok(())),
+    };
+
+    let mut select_primitive_body = TokenStream::new();
+    let mut variant_body = TokenStream::new();
+    for (variant, ty, deref) in &primitive_variants {
+        select_primitive_body.extend(quote! {
+            if Primitive::kind(&#deref <#ty as Default>::default()).accepts(kind) {
+                match *self {
+                    #variant(_) => {}
+                    _ => *self = #variant(Default::default()),
+                }
+                return match *self {
+                    #variant(ref mut v) => Some(&mut #deref *v),
+                    _ => None,
+                };
+            }
+        });
+        variant_body.extend(quote! {
+            #variant(ref v) => Some(FieldRefReflection::Primitive(&#deref *v)),
+        });
+    }
+    let select_list_body = match list_variants.first() {
+        Some((variant, _, deref)) => quote! {
+            match *self {
+                #variant(_) => {}
+                _ => *self = #variant(Default::default()),
+            }
+            match *self {
+                #variant(ref mut v) => Some(&mut #deref *v),
+                _ => None,
+            }
+        },
+        None => quote!(None),
+    };
+    for (variant, _, deref) in &list_variants {
+        variant_body.extend(quote! {
+            #variant(ref v) => Some(FieldRefReflection::List(&#deref *v)),
+        });
+    }
+
+    let object_tys = &object_tys;
+    let tys = variants
+        .iter()
+        .filter_map(|variant| variant.field.as_ref())
+        .map(|(field, _)| &field.ty);
+    let mut tokens = s.gen_impl(quote! {
+        use #krate::reflection::{
+            List, Object, ObjectFields, FieldMutReflection, Primitive, PrimitiveValueKind,
+            ReflectionError,
+        };
+
+        #[allow(unreachable_code, unreachable_patterns)]
+        gen impl Object for @Self where #(#tys: Default,)* #(#object_tys: ObjectFields),* {
+            fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+                match *self {
+                    #create_body
+                    _ => Err(ReflectionError::InvalidField { name: field_name.to_string() }),
+                }
+            }
+
+            fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+                match *self {
+                    #set_null_body
+                    _ => Err(ReflectionError::InvalidField { name: field_name.to_string() }),
+                }
+            }
+
+            fn discard(&mut self, field_name: &str) {
+                match *self {
+                    #discard_body
+                    _ => {}
+                }
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                match *self {
+                    #field_names_body
+                    _ => &[],
+                }
+            }
+
+            fn required_fields(&self) -> &'static [&'static str] {
+                match *self {
+                    #required_fields_body
+                    _ => &[],
+                }
+            }
+
+            fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+                match *self {
+                    #aliases_body
+                    _ => &[],
+                }
+            }
+
+            fn select_variant(&mut self, field_names: &[&str]) -> bool {
+                let mut selected = None;
+                #select_variant_body
+                match selected {
+                    #select_variant_arms
+                    _ => return false,
+                }
+                true
+            }
+
+            fn select_default_variant(
+                &mut self,
+                field_names: &[&str],
+            ) -> Result<(), ReflectionError> {
+                #select_default_variant_body
+            }
+
+            fn select_primitive(&mut self, kind: PrimitiveValueKind) -> Option<&mut dyn Primitive> {
+                #select_primitive_body
+                None
+            }
+
+            fn select_list(&mut self) -> Option<&mut dyn List> {
+                #select_list_body
+            }
+        }
+    });
+    // Unions know the fields of all their object variants
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::ObjectFields;
+
+        gen impl ObjectFields for @Self where #(#object_tys: ObjectFields),* {
+            fn knows_fields(field_names: &[&str]) -> bool {
+                #knows_fields_body
+            }
+
+            fn missing_fields(field_names: &[&str]) -> Vec<&'static str> {
+                let mut missing = Vec::new();
+                #missing_fields_body
+                missing
+            }
+        }
+    }));
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{ObjectRef, FieldRefReflection};
+
+        #[allow(unreachable_patterns)]
+        gen impl ObjectRef for @Self {
+            fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+                match *self {
+                    #fields_body
+                    _ => Vec::new(),
+                }
+            }
+
+            fn variant(&self) -> Option<FieldRefReflection> {
+                match *self {
+                    #variant_body
+                    _ => None,
+                }
+            }
+        }
+    }));
+    Ok(tokens)
+}
+
+/// Names of all variants, as a static slice expression.
+fn variant_names(variants: &[Variant]) -> TokenStream {
+    let names = variants.iter().map(|variant| &variant.name);
+    quote!(&[#(#names),*])
+}
@@ -1,13 +1,18 @@
 #![recursion_limit = "192"]
 
-use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::parse::Error;
 use syn::spanned::Spanned;
-use syn::{Data, Field, Type};
+use syn::{
+    Attribute, Data, DataStruct, Field, GenericArgument, Ident, Lit, LitStr, Meta, MetaNameValue,
+    NestedMeta, Path, PathArguments, Type,
+};
 use synstructure::decl_derive;
 
-decl_derive!([Object, attributes(default, primitive, any)] => derive_object);
+mod enums;
+
+decl_derive!([Object, attributes(default, primitive, any, required, dynser)] => derive_object);
 
 #[allow(clippy::needless_pass_by_value)]
 fn derive_object(s: synstructure::Structure) -> TokenStream {
@@ -18,12 +23,44 @@ fn derive_object(s: synstructure::Structure) -> TokenStream {
 }
 
 fn derive_object_inner(s: &synstructure::Structure) -> Result<TokenStream, Error> {
-    let ds = match s.ast().data {
-        Data::Struct(ref ds) => ds,
-        _ => return Err(Error::new(s.ast().span(), "can only derive on structs")),
-    };
+    let options = ContainerOptions::parse(&s.ast().attrs)?;
+    match s.ast().data {
+        Data::Struct(ref ds) => {
+            if let Some(ref tag) = options.tag {
+                return Err(Error::new(tag.span(), "tag is only supported on enums"));
+            }
+            if let Some(ref untagged) = options.untagged {
+                return Err(Error::new(
+                    untagged.span(),
+                    "untagged is only supported on enums",
+                ));
+            }
+            derive_struct(s, ds, &options)
+        }
+        Data::Enum(ref de) => enums::derive_enum(s, de, &options),
+        Data::Union(_) => Err(Error::new(
+            s.ast().span(),
+            "can only derive on structs and enums",
+        )),
+    }
+}
+
+fn derive_struct(
+    s: &synstructure::Structure,
+    ds: &DataStruct,
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let krate = &options.krate;
+    let rename_all = options.rename_all;
 
     let mut create_body = TokenStream::new();
+    let mut fields_body = TokenStream::new();
+    let mut set_null_body = TokenStream::new();
+    let mut discard_body = TokenStream::new();
+    let mut field_names = Vec::new();
+    let mut required_fields = Vec::new();
+    let mut aliases = Vec::new();
+    let mut all_names: Vec<String> = Vec::new();
     let mut default_field = None;
     for field in ds.fields.iter() {
         let ident = field.ident.as_ref().ok_or_else(|| {
@@ -32,9 +69,15 @@ fn derive_object_inner(s: &synstructure::Structure) -> Result<TokenStream, Error
                 "can only derive on structs with named fields",
             )
         })?;
-        let field_name = field_name(field);
+        let options = FieldOptions::parse(&field.attrs)?;
+        let field_name = options.field_name(field, rename_all);
+        check_marker_attributes(field)?;
 
         let is_default = field.attrs.iter().any(|attr| attr.path.is_ident("default"));
+        let is_required = field
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("required"));
 
         if is_default {
             if default_field.is_some() {
@@ -43,51 +86,90 @@ fn derive_object_inner(s: &synstructure::Structure) -> Result<TokenStream, Error
                     "can only have one #[default] field",
                 ));
             }
+            if is_required {
+                return Err(Error::new(
+                    field.ident.span(),
+                    "#[default] field cannot be #[required]",
+                ));
+            }
+            if let Some(alias) = options.aliases.first() {
+                return Err(Error::new(
+                    alias.span(),
+                    "#[default] field cannot have aliases",
+                ));
+            }
             default_field = Some(ident);
             continue;
         }
 
-        let is_primitive = field
-            .attrs
-            .iter()
-            .any(|attr| attr.path.is_ident("primitive"));
-        let is_any = field.attrs.iter().any(|attr| attr.path.is_ident("any"));
+        options.check_unique(&mut all_names, &field_name, field.ident.span())?;
+        for alias in &options.aliases {
+            aliases.push(quote!((#alias, #field_name)));
+        }
+        let alias_names = &options.aliases;
 
-        let is_vec = is_wrapped_by(&field.ty, "Vec");
-        let is_option = is_wrapped_by(&field.ty, "Option");
+        field_names.push(field_name.clone());
+        if is_required {
+            required_fields.push(field_name.clone());
+        }
 
-        // Which FieldMutReflection:: variant to use.
-        let tag = if is_vec {
-            quote!(List)
-        } else if is_primitive {
-            quote!(Primitive)
-        } else if is_any {
-            quote!(Any)
-        } else {
-            quote!(Object)
-        };
+        let shape = FieldShape::parse(&field.ty)?;
+        let tag = shape.reflection(field);
+        let ref_tag = tag.read_only();
+        let deref = shape.deref();
 
-        if is_option {
+        if shape.optional {
             let span = ident.span();
             create_body.extend(quote_spanned!(span =>
-              #field_name => {
+              #field_name #(| #alias_names)* => {
                 match self.#ident {
-                  Some(ref mut v) => FieldMutReflection::#tag(v),
+                  Some(ref mut v) => FieldMutReflection::#tag(&mut #deref *v),
                   None => {
                     self.#ident = Some(Default::default());
-                    FieldMutReflection::#tag(self.#ident.as_mut().unwrap())
+                    FieldMutReflection::#tag(&mut #deref *self.#ident.as_mut().unwrap())
                   }
                 }
               }
             ));
+            fields_body.extend(quote_spanned!(span =>
+              if let Some(ref v) = self.#ident {
+                fields.push((#field_name, FieldRefReflection::#ref_tag(&#deref *v)));
+              }
+            ));
+            set_null_body.extend(quote_spanned!(span =>
+              #field_name #(| #alias_names)* => self.#ident = None,
+            ));
+            discard_body.extend(quote_spanned!(span =>
+              #field_name #(| #alias_names)* => self.#ident = None,
+            ));
         } else {
             let span = ident.span();
             create_body.extend(quote_spanned!(span =>
-              #field_name => FieldMutReflection::#tag(&mut self.#ident),
+              #field_name #(| #alias_names)* => FieldMutReflection::#tag(&mut #deref self.#ident),
+            ));
+            fields_body.extend(quote_spanned!(span =>
+              fields.push((#field_name, FieldRefReflection::#ref_tag(&#deref self.#ident)));
             ));
+            if tag == Reflection::Object || tag == Reflection::Any {
+                // Objects could be nullable themselves (like JSON values)
+                set_null_body.extend(quote_spanned!(span =>
+                  #field_name #(| #alias_names)* => return Object::make_null(&mut #deref self.#ident),
+                ));
+            } else {
+                set_null_body.extend(quote_spanned!(span =>
+                  #field_name #(| #alias_names)* => return Err(ReflectionError::NotNullable),
+                ));
+            }
         }
     }
 
+    if let Some(ident) = default_field {
+        let span = ident.span();
+        fields_body.extend(quote_spanned!(span =>
+          fields.extend(self.#ident.fields());
+        ));
+    }
+
     let default_match = default_field
         .map(|ident| {
             let span = ident.span();
@@ -98,9 +180,25 @@ fn derive_object_inner(s: &synstructure::Structure) -> Result<TokenStream, Error
                 _ => { return Err(ReflectionError::InvalidField { name: field_name.to_string() }) }
             }
         });
+    let default_discard = default_field
+        .map(|ident| {
+            let span = ident.span();
+            quote_spanned!(span => name => self.#ident.discard(name),)
+        })
+        .unwrap_or_else(|| quote!(_ => {}));
+    let default_set_null = default_field
+        .map(|ident| {
+            let span = ident.span();
+            quote_spanned!(span => name => { return self.#ident.set_null(name) })
+        })
+        .unwrap_or_else(|| {
+            quote! {
+                _ => { return Err(ReflectionError::InvalidField { name: field_name.to_string() }) }
+            }
+        });
 
-    Ok(s.gen_impl(quote! {
-        use crate::reflection::{Object, FieldMutReflection, ReflectionError};
+    let mut tokens = s.gen_impl(quote! {
+        use #krate::reflection::{Object, ObjectFields, FieldMutReflection, ReflectionError};
 
         #[allow(unreachable_code)]
         gen impl Object for @Self {
@@ -110,20 +208,515 @@ fn derive_object_inner(s: &synstructure::Structure) -> Result<TokenStream, Error
                     #default_match
                 })
             }
+
+            fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+                match field_name {
+                    #set_null_body
+                    #default_set_null
+                }
+                Ok(())
+            }
+
+            fn discard(&mut self, field_name: &str) {
+                match field_name {
+                    #discard_body
+                    #default_discard
+                }
+            }
+
+            fn field_names(&self) -> &'static [&'static str] {
+                <Self as ObjectFields>::FIELD_NAMES
+            }
+
+            fn required_fields(&self) -> &'static [&'static str] {
+                <Self as ObjectFields>::REQUIRED_FIELDS
+            }
+
+            fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+                <Self as ObjectFields>::ALIASES
+            }
         }
-    }))
+    });
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::ObjectFields;
+
+        gen impl ObjectFields for @Self {
+            const FIELD_NAMES: &'static [&'static str] = &[#(#field_names),*];
+            const REQUIRED_FIELDS: &'static [&'static str] = &[#(#required_fields),*];
+            const ALIASES: &'static [(&'static str, &'static str)] = &[#(#aliases),*];
+        }
+    }));
+    tokens.extend(s.gen_impl(quote! {
+        use #krate::reflection::{ObjectRef, FieldRefReflection};
+
+        gen impl ObjectRef for @Self {
+            fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+                let mut fields = Vec::new();
+                #fields_body
+                fields
+            }
+        }
+    }));
+    Ok(tokens)
 }
 
-fn field_name(field: &Field) -> String {
-    let ident = field.ident.as_ref().expect("field name expected");
-    ident.to_string().trim_start_matches("r#").to_owned()
+/// Options given to the struct or enum via `#[dynser(...)]` attributes.
+struct ContainerOptions {
+    /// Path to the `dynser` crate.
+    krate: TokenStream,
+    rename_all: Option<RenameRule>,
+    /// Name of the field holding the variant name of internally tagged enums.
+    tag: Option<LitStr>,
+    /// Enum is an untagged union, see `enums::derive_untagged`.
+    untagged: Option<Ident>,
 }
 
-fn is_wrapped_by(ty: &Type, type_name: &str) -> bool {
-    match ty {
-        Type::Path(tp) if tp.path.leading_colon.is_none() && tp.path.segments.len() == 1 => {
-            tp.path.segments.first().as_ref().unwrap().value().ident == type_name
+impl ContainerOptions {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut options = ContainerOptions {
+            krate: default_crate_path(),
+            rename_all: None,
+            tag: None,
+            untagged: None,
+        };
+        for option in dynser_options(attrs)? {
+            match option {
+                Meta::NameValue(ref option) if option.ident == "rename_all" => {
+                    options.rename_all = Some(RenameRule::parse(option)?);
+                }
+                Meta::NameValue(ref option) if option.ident == "crate" => {
+                    let path: Path = lit_str(option)?.parse()?;
+                    options.krate = quote!(#path);
+                }
+                Meta::NameValue(ref option) if option.ident == "tag" => {
+                    options.tag = Some(lit_str(option)?);
+                }
+                Meta::Word(ref word) if word == "untagged" => {
+                    options.untagged = Some(word.clone());
+                }
+                other => return Err(Error::new(other.span(), "unknown dynser option")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Path to the `dynser` crate, unless overridden with `#[dynser(crate = "...")]`. Inside `dynser`
+/// itself the crate cannot be named (`extern crate self as dynser` would clash with its `dynser`
+/// module), so `crate` is used there.
+fn default_crate_path() -> TokenStream {
+    if std::env::var("CARGO_CRATE_NAME").is_ok_and(|name| name == "dynser") {
+        quote!(crate)
+    } else {
+        quote!(::dynser)
+    }
+}
+
+/// Options given to the field or enum variant via `#[dynser(...)]` attributes.
+struct FieldOptions {
+    rename: Option<String>,
+    aliases: Vec<LitStr>,
+}
+
+impl FieldOptions {
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut options = FieldOptions {
+            rename: None,
+            aliases: Vec::new(),
+        };
+        for option in dynser_options(attrs)? {
+            match option {
+                Meta::NameValue(ref option) if option.ident == "rename" => {
+                    options.rename = Some(lit_str(option)?.value());
+                }
+                Meta::NameValue(ref option) if option.ident == "alias" => {
+                    options.aliases.push(lit_str(option)?);
+                }
+                other => return Err(Error::new(other.span(), "unknown dynser option")),
+            }
+        }
+        Ok(options)
+    }
+
+    fn field_name(&self, field: &Field, rename_all: Option<RenameRule>) -> String {
+        let ident = field.ident.as_ref().expect("field name expected");
+        let name = ident.to_string().trim_start_matches("r#").to_owned();
+        match (&self.rename, rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply(&name),
+            (None, None) => name,
+        }
+    }
+
+    /// Check that neither the name nor any of the aliases is used by another field (or variant),
+    /// recording them into `all_names`.
+    fn check_unique(
+        &self,
+        all_names: &mut Vec<String>,
+        name: &str,
+        span: Span,
+    ) -> Result<(), Error> {
+        if all_names.iter().any(|used| used == name) {
+            return Err(Error::new(
+                span,
+                format!("field name `{}` is already used", name),
+            ));
+        }
+        all_names.push(name.to_owned());
+        for alias in &self.aliases {
+            if all_names.contains(&alias.value()) {
+                return Err(Error::new(
+                    alias.span(),
+                    format!("field name `{}` is already used", alias.value()),
+                ));
+            }
+            all_names.push(alias.value());
         }
-        _ => false,
+        Ok(())
+    }
+}
+
+/// Collect all `name = "value"` and `name` options given via `#[dynser(...)]` attributes.
+fn dynser_options(attrs: &[Attribute]) -> Result<Vec<Meta>, Error> {
+    let mut options = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("dynser")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(option @ Meta::NameValue(_))
+                        | NestedMeta::Meta(option @ Meta::Word(_)) => options.push(option),
+                        other => {
+                            return Err(Error::new(other.span(), "expected `name = \"value\"`"))
+                        }
+                    }
+                }
+            }
+            other => return Err(Error::new(other.span(), "expected #[dynser(...)]")),
+        }
+    }
+    Ok(options)
+}
+
+fn lit_str(option: &MetaNameValue) -> Result<LitStr, Error> {
+    match option.lit {
+        Lit::Str(ref lit) => Ok(lit.clone()),
+        ref lit => Err(Error::new(lit.span(), "expected string literal")),
+    }
+}
+
+/// Rule to derive wire names from `snake_case` field names.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(option: &MetaNameValue) -> Result<Self, Error> {
+        Ok(match lit_str(option)?.value().as_str() {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => {
+                return Err(Error::new(
+                    option.lit.span(),
+                    "unknown rename rule, expected one of \"lowercase\", \"UPPERCASE\", \
+                     \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                     \"kebab-case\", \"SCREAMING-KEBAB-CASE\"",
+                ))
+            }
+        })
+    }
+
+    fn apply(self, name: &str) -> String {
+        match self {
+            RenameRule::Lower | RenameRule::Snake => name.to_owned(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => name.to_ascii_uppercase(),
+            RenameRule::Pascal => name
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                        None => String::new(),
+                    }
+                })
+                .collect(),
+            RenameRule::Camel => {
+                let pascal = RenameRule::Pascal.apply(name);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Kebab => name.replace('_', "-"),
+            RenameRule::ScreamingKebab => name.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+
+    /// Same as `apply`, but for `PascalCase` variant names.
+    fn apply_to_variant(self, name: &str) -> String {
+        match self {
+            RenameRule::Lower => name.to_ascii_lowercase(),
+            RenameRule::Upper => name.to_ascii_uppercase(),
+            RenameRule::Pascal => name.to_owned(),
+            RenameRule::Camel => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            _ => {
+                let mut snake = String::new();
+                for (idx, ch) in name.char_indices() {
+                    if idx > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                self.apply(&snake)
+            }
+        }
+    }
+}
+
+/// Reject duplicated and conflicting marker attributes, like `#[primitive]` given twice or
+/// `#[primitive]` combined with `#[any]`.
+fn check_marker_attributes(field: &Field) -> Result<(), Error> {
+    let mut seen: Vec<&Attribute> = Vec::new();
+    for attr in &field.attrs {
+        let name = match ["default", "primitive", "any", "required"]
+            .iter()
+            .find(|name| attr.path.is_ident(name))
+        {
+            Some(name) => name,
+            None => continue,
+        };
+        if seen.iter().any(|prev| prev.path.is_ident(name)) {
+            return Err(Error::new(
+                attr.span(),
+                format!("duplicate #[{}] attribute", name),
+            ));
+        }
+        let conflict = match *name {
+            "primitive" => "any",
+            "any" => "primitive",
+            _ => "",
+        };
+        if seen.iter().any(|prev| prev.path.is_ident(conflict)) {
+            return Err(Error::new(
+                attr.span(),
+                format!("#[{}] conflicts with #[{}]", name, conflict),
+            ));
+        }
+        seen.push(attr);
+    }
+    Ok(())
+}
+
+/// Shape of the field type, as seen by the reflection.
+struct FieldShape {
+    /// Field is wrapped into an `Option`.
+    optional: bool,
+    /// Number of `Box` wrappers to dereference to get to the value.
+    boxes: usize,
+    kind: ShapeKind,
+}
+
+/// Variant of `FieldMutReflection` (and `FieldRefReflection`), tokenized as the variant name.
+#[derive(Clone, Copy, PartialEq)]
+enum Reflection {
+    Object,
+    List,
+    Primitive,
+    Any,
+    /// Only used for `FieldMutReflection`, these are plain objects when read.
+    Polymorphic,
+}
+
+impl Reflection {
+    /// Variant of `FieldRefReflection` to use.
+    fn read_only(self) -> Reflection {
+        match self {
+            Reflection::Polymorphic => Reflection::Object,
+            reflection => reflection,
+        }
+    }
+}
+
+impl ToTokens for Reflection {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Reflection::Object => quote!(Object),
+            Reflection::List => quote!(List),
+            Reflection::Primitive => quote!(Primitive),
+            Reflection::Any => quote!(Any),
+            Reflection::Polymorphic => quote!(Polymorphic),
+        });
+    }
+}
+
+enum ShapeKind {
+    List,
+    Map,
+    Primitive,
+    /// `Discriminated` objects of the type selected at runtime.
+    Polymorphic,
+    Other,
+}
+
+impl FieldShape {
+    fn parse(ty: &Type) -> Result<Self, Error> {
+        let (optional, mut ty) = match wrapped_type(ty, "Option") {
+            Some(inner) => (true, inner),
+            None => (false, ty),
+        };
+        let mut boxes = 0;
+        while let Some(inner) = wrapped_type(ty, "Box") {
+            boxes += 1;
+            ty = inner;
+        }
+        let kind = if let Some(item) = wrapped_type(ty, "Vec") {
+            check_list_item(item)?;
+            ShapeKind::List
+        } else if let Some(value) = map_value_type(ty) {
+            check_element_type(value)?;
+            ShapeKind::Map
+        } else {
+            check_element_type(ty)?;
+            if is_known_primitive(ty) {
+                ShapeKind::Primitive
+            } else if type_arguments(ty, "Discriminated").is_some_and(|args| args.is_empty()) {
+                ShapeKind::Polymorphic
+            } else {
+                ShapeKind::Other
+            }
+        };
+        Ok(FieldShape {
+            optional,
+            boxes,
+            kind,
+        })
+    }
+
+    /// Which `FieldMutReflection` (and `FieldRefReflection`) variant to use for the field.
+    fn reflection(&self, field: &Field) -> Reflection {
+        let is_primitive = field
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("primitive"));
+        let is_any = field.attrs.iter().any(|attr| attr.path.is_ident("any"));
+        match self.kind {
+            ShapeKind::List => Reflection::List,
+            ShapeKind::Map => Reflection::Object,
+            _ if is_any => Reflection::Any,
+            ShapeKind::Primitive => Reflection::Primitive,
+            ShapeKind::Polymorphic => Reflection::Polymorphic,
+            ShapeKind::Other if is_primitive => Reflection::Primitive,
+            ShapeKind::Other => Reflection::Object,
+        }
+    }
+
+    /// Dereference boxes to get to the value itself.
+    fn deref(&self) -> TokenStream {
+        (0..self.boxes).map(|_| quote!(*)).collect()
+    }
+}
+
+/// Check type which is not the outermost type of the field.
+fn check_element_type(ty: &Type) -> Result<(), Error> {
+    if wrapped_type(ty, "Option").is_some() {
+        return Err(Error::new(
+            ty.span(),
+            "`Option` is only supported as the outermost type of the field",
+        ));
+    }
+    if wrapped_type(ty, "Box").is_some() {
+        return Err(Error::new(
+            ty.span(),
+            "`Box` is only supported around the field type, not inside `Vec` or `HashMap`",
+        ));
+    }
+    match ty {
+        Type::Path(tp) if tp.qself.is_none() => {}
+        _ => return Err(Error::new(ty.span(), "unsupported field type")),
+    }
+    if let Some(item) = wrapped_type(ty, "Vec") {
+        check_list_item(item)?;
+    } else if let Some(value) = map_value_type(ty) {
+        check_element_type(value)?;
+    }
+    Ok(())
+}
+
+/// Check type of the list items. Lists of lists are not supported.
+fn check_list_item(ty: &Type) -> Result<(), Error> {
+    if wrapped_type(ty, "Vec").is_some() {
+        return Err(Error::new(ty.span(), "`Vec` of `Vec` is not supported"));
+    }
+    check_element_type(ty)
+}
+
+/// Check if type is one of the known primitives. Other types still could be primitives, but
+/// need to be marked with `#[primitive]` explicitly.
+fn is_known_primitive(ty: &Type) -> bool {
+    ["String", "bool", "i32", "i64", "u32", "u64", "f64"]
+        .iter()
+        .any(|name| type_arguments(ty, name).is_some_and(|args| args.is_empty()))
+}
+
+/// Get the value type of the `HashMap<K, V>` type.
+fn map_value_type(ty: &Type) -> Option<&Type> {
+    match type_arguments(ty, "HashMap") {
+        Some(ref args) if args.len() == 2 => Some(args[1]),
+        _ => None,
+    }
+}
+
+/// Get the type argument of the `type_name<T>` type.
+fn wrapped_type<'a>(ty: &'a Type, type_name: &str) -> Option<&'a Type> {
+    match type_arguments(ty, type_name) {
+        Some(ref args) if args.len() == 1 => Some(args[0]),
+        _ => None,
+    }
+}
+
+/// Get type arguments if type is a path (possibly qualified, like `std::vec::Vec<T>`) ending with
+/// the `type_name` segment.
+fn type_arguments<'a>(ty: &'a Type, type_name: &str) -> Option<Vec<&'a Type>> {
+    let tp = match ty {
+        Type::Path(tp) if tp.qself.is_none() => tp,
+        _ => return None,
+    };
+    let segment = tp.path.segments.last()?.into_value();
+    if segment.ident != type_name {
+        return None;
+    }
+    match segment.arguments {
+        PathArguments::None => Some(Vec::new()),
+        PathArguments::AngleBracketed(ref args) => Some(
+            args.args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        PathArguments::Parenthesized(_) => None,
     }
 }
@@ -0,0 +1,380 @@
+//! Dynamic values, which take the shape of the incoming data: objects, lists or primitives.
+//!
+//! `DynamicObject` is the value of the `#[any]` fields, independent of the wire format.
+//! `serde_json::Value` is supported the same way and could be converted to and from
+//! `DynamicObject`.
+use crate::reflection::{
+    FieldMutReflection, FieldRefReflection, List, Object, ObjectRef, Primitive, PrimitiveRef,
+    PrimitiveValue, PrimitiveValueKind, PrimitiveValueRef, ReflectionError,
+};
+use serde::de::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicObject {
+    Null,
+    Primitive(PrimitiveValue),
+    List(Vec<DynamicObject>),
+    Object(BTreeMap<String, DynamicObject>),
+}
+
+impl Default for DynamicObject {
+    fn default() -> Self {
+        DynamicObject::Null
+    }
+}
+
+impl DynamicObject {
+    pub fn is_null(&self) -> bool {
+        *self == DynamicObject::Null
+    }
+
+    pub fn as_primitive(&self) -> Option<&PrimitiveValue> {
+        match self {
+            DynamicObject::Primitive(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<DynamicObject>> {
+        match self {
+            DynamicObject::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<DynamicObject>> {
+        match self {
+            DynamicObject::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, DynamicObject>> {
+        match self {
+            DynamicObject::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut BTreeMap<String, DynamicObject>> {
+        match self {
+            DynamicObject::Object(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Turn value into an object (unless it is already one).
+    fn object_mut(&mut self) -> &mut BTreeMap<String, DynamicObject> {
+        if self.as_object().is_none() {
+            *self = DynamicObject::Object(BTreeMap::new());
+        }
+        self.as_object_mut().unwrap()
+    }
+
+    /// Turn value into a list (unless it is already one).
+    fn list_mut(&mut self) -> &mut Vec<DynamicObject> {
+        if self.as_list().is_none() {
+            *self = DynamicObject::List(Vec::new());
+        }
+        self.as_list_mut().unwrap()
+    }
+}
+
+impl Object for DynamicObject {
+    fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+        let child = self
+            .object_mut()
+            .entry(field_name.to_owned())
+            .or_insert(DynamicObject::Null);
+        Ok(FieldMutReflection::Any(child))
+    }
+
+    fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+        self.object_mut()
+            .insert(field_name.to_owned(), DynamicObject::Null);
+        Ok(())
+    }
+
+    fn discard(&mut self, field_name: &str) {
+        self.object_mut().remove(field_name);
+    }
+
+    fn make_null(&mut self) -> Result<(), ReflectionError> {
+        *self = DynamicObject::Null;
+        Ok(())
+    }
+
+    fn select_variant(&mut self, _field_names: &[&str]) -> bool {
+        self.object_mut();
+        true
+    }
+
+    fn select_default_variant(&mut self, _field_names: &[&str]) -> Result<(), ReflectionError> {
+        self.object_mut();
+        Ok(())
+    }
+
+    fn select_primitive(&mut self, _kind: PrimitiveValueKind) -> Option<&mut dyn Primitive> {
+        Some(self)
+    }
+
+    fn select_list(&mut self) -> Option<&mut dyn List> {
+        self.list_mut();
+        Some(self)
+    }
+}
+
+impl List for DynamicObject {
+    fn push(&mut self) -> FieldMutReflection {
+        let items = self.list_mut();
+        items.push(DynamicObject::Null);
+        FieldMutReflection::Any(items.last_mut().unwrap())
+    }
+
+    fn push_null(&mut self) -> Result<(), ReflectionError> {
+        self.list_mut().push(DynamicObject::Null);
+        Ok(())
+    }
+
+    fn discard_last(&mut self) {
+        self.list_mut().pop();
+    }
+}
+
+impl Primitive for DynamicObject {
+    fn kind(&self) -> PrimitiveValueKind {
+        match self {
+            DynamicObject::Primitive(value) => value.kind(),
+            _ => PrimitiveValueKind::String,
+        }
+    }
+
+    fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError> {
+        *self = DynamicObject::Primitive(value);
+        Ok(())
+    }
+}
+
+impl ObjectRef for DynamicObject {
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+        match self {
+            DynamicObject::Object(fields) => fields
+                .iter()
+                .map(|(name, value)| (name.as_str(), FieldRefReflection::Any(value)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn variant(&self) -> Option<FieldRefReflection> {
+        match self {
+            DynamicObject::Null => Some(FieldRefReflection::Null),
+            DynamicObject::Primitive(value) => Some(FieldRefReflection::Primitive(value)),
+            DynamicObject::List(items) => Some(FieldRefReflection::List(items)),
+            DynamicObject::Object(_) => None,
+        }
+    }
+}
+
+/// Deserialized via `serde_json::Value`, for the types deserialized by serde directly.
+impl<'de> Deserialize<'de> for DynamicObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_json::Value::deserialize(deserializer).map(DynamicObject::from)
+    }
+}
+
+impl From<serde_json::Value> for DynamicObject {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => DynamicObject::Null,
+            serde_json::Value::Bool(v) => DynamicObject::Primitive(PrimitiveValue::Bool(v)),
+            serde_json::Value::Number(v) => DynamicObject::Primitive(if let Some(v) = v.as_u64() {
+                PrimitiveValue::U64(v)
+            } else if let Some(v) = v.as_i64() {
+                PrimitiveValue::I64(v)
+            } else {
+                PrimitiveValue::F64(v.as_f64().unwrap_or_default())
+            }),
+            serde_json::Value::String(v) => DynamicObject::Primitive(PrimitiveValue::String(v)),
+            serde_json::Value::Array(items) => {
+                DynamicObject::List(items.into_iter().map(DynamicObject::from).collect())
+            }
+            serde_json::Value::Object(fields) => DynamicObject::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, DynamicObject::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Non-finite numbers become `null`, as JSON has no representation for them.
+impl From<DynamicObject> for serde_json::Value {
+    fn from(value: DynamicObject) -> Self {
+        match value {
+            DynamicObject::Null => serde_json::Value::Null,
+            DynamicObject::Primitive(PrimitiveValue::String(v)) => serde_json::Value::String(v),
+            DynamicObject::Primitive(PrimitiveValue::Bool(v)) => serde_json::Value::Bool(v),
+            DynamicObject::Primitive(PrimitiveValue::I64(v)) => serde_json::Value::from(v),
+            DynamicObject::Primitive(PrimitiveValue::U64(v)) => serde_json::Value::from(v),
+            DynamicObject::Primitive(PrimitiveValue::F64(v)) => serde_json::Value::from(v),
+            DynamicObject::List(items) => {
+                serde_json::Value::Array(items.into_iter().map(serde_json::Value::from).collect())
+            }
+            DynamicObject::Object(fields) => serde_json::Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// serde_json::Value
+
+/// Turn value into an object (unless it is already one).
+fn json_object(value: &mut serde_json::Value) -> &mut serde_json::Map<String, serde_json::Value> {
+    if !value.is_object() {
+        *value = serde_json::Value::Object(serde_json::Map::new());
+    }
+    value.as_object_mut().unwrap()
+}
+
+/// Turn value into an array (unless it is already one).
+fn json_array(value: &mut serde_json::Value) -> &mut Vec<serde_json::Value> {
+    if !value.is_array() {
+        *value = serde_json::Value::Array(Vec::new());
+    }
+    value.as_array_mut().unwrap()
+}
+
+impl Object for serde_json::Value {
+    fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+        let child = json_object(self)
+            .entry(field_name)
+            .or_insert(serde_json::Value::Null);
+        Ok(FieldMutReflection::Object(child))
+    }
+
+    fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+        json_object(self).insert(field_name.to_owned(), serde_json::Value::Null);
+        Ok(())
+    }
+
+    fn discard(&mut self, field_name: &str) {
+        json_object(self).remove(field_name);
+    }
+
+    fn make_null(&mut self) -> Result<(), ReflectionError> {
+        *self = serde_json::Value::Null;
+        Ok(())
+    }
+
+    fn select_variant(&mut self, _field_names: &[&str]) -> bool {
+        json_object(self);
+        true
+    }
+
+    fn select_default_variant(&mut self, _field_names: &[&str]) -> Result<(), ReflectionError> {
+        json_object(self);
+        Ok(())
+    }
+
+    fn select_primitive(&mut self, _kind: PrimitiveValueKind) -> Option<&mut dyn Primitive> {
+        Some(self)
+    }
+
+    fn select_list(&mut self) -> Option<&mut dyn List> {
+        json_array(self);
+        Some(self)
+    }
+}
+
+impl List for serde_json::Value {
+    fn push(&mut self) -> FieldMutReflection {
+        let items = json_array(self);
+        items.push(serde_json::Value::Null);
+        FieldMutReflection::Object(items.last_mut().unwrap())
+    }
+
+    fn push_null(&mut self) -> Result<(), ReflectionError> {
+        json_array(self).push(serde_json::Value::Null);
+        Ok(())
+    }
+
+    fn discard_last(&mut self) {
+        json_array(self).pop();
+    }
+}
+
+impl Primitive for serde_json::Value {
+    fn kind(&self) -> PrimitiveValueKind {
+        match self {
+            serde_json::Value::Bool(_) => PrimitiveValueKind::Bool,
+            serde_json::Value::Number(n) if n.is_i64() => PrimitiveValueKind::I64,
+            serde_json::Value::Number(n) if n.is_u64() => PrimitiveValueKind::U64,
+            serde_json::Value::Number(_) => PrimitiveValueKind::F64,
+            _ => PrimitiveValueKind::String,
+        }
+    }
+
+    fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError> {
+        *self = match value {
+            PrimitiveValue::String(v) => serde_json::Value::String(v),
+            PrimitiveValue::Bool(v) => serde_json::Value::Bool(v),
+            PrimitiveValue::I64(v) => serde_json::Value::Number(v.into()),
+            PrimitiveValue::U64(v) => serde_json::Value::Number(v.into()),
+            PrimitiveValue::F64(v) => match serde_json::Number::from_f64(v) {
+                Some(number) => serde_json::Value::Number(number),
+                None => {
+                    return Err(ReflectionError::OutOfRange {
+                        expected: "f64",
+                        value: v.to_string(),
+                    })
+                }
+            },
+        };
+        Ok(())
+    }
+}
+
+impl ObjectRef for serde_json::Value {
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+        match self {
+            serde_json::Value::Object(map) => map
+                .iter()
+                .map(|(name, value)| (name.as_str(), FieldRefReflection::Object(value)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn variant(&self) -> Option<FieldRefReflection> {
+        match self {
+            serde_json::Value::Null => Some(FieldRefReflection::Null),
+            serde_json::Value::Bool(v) => Some(FieldRefReflection::Primitive(v)),
+            serde_json::Value::Number(v) => Some(FieldRefReflection::Primitive(v)),
+            serde_json::Value::String(v) => Some(FieldRefReflection::Primitive(v)),
+            serde_json::Value::Array(items) => Some(FieldRefReflection::List(items)),
+            serde_json::Value::Object(_) => None,
+        }
+    }
+}
+
+impl PrimitiveRef for serde_json::Number {
+    fn value(&self) -> PrimitiveValueRef {
+        if let Some(v) = self.as_u64() {
+            PrimitiveValueRef::U64(v)
+        } else if let Some(v) = self.as_i64() {
+            PrimitiveValueRef::I64(v)
+        } else {
+            PrimitiveValueRef::F64(self.as_f64().unwrap_or_default())
+        }
+    }
+}
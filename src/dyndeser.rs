@@ -1,55 +1,992 @@
-use crate::reflection::{FieldMutReflection, List, Object, Primitive, PrimitiveValue};
+use crate::reflection::{
+    FieldMutReflection, List, Object, Polymorphic, Primitive, PrimitiveValue, PrimitiveValueKind,
+    ReflectionError,
+};
+use crate::registry::TypeRegistry;
+use failure::Fail;
 use serde::de::{
-    self, Deserialize, DeserializeSeed, Deserializer, Error, MapAccess, SeqAccess, Unexpected,
-    Visitor,
+    self, Deserialize, DeserializeSeed, Deserializer, Expected, IgnoredAny, MapAccess, SeqAccess,
+    Unexpected, Visitor,
 };
-use serde_json::de::{Deserializer as JsonDeserializer, StrRead};
+use serde_json::de::{Deserializer as JsonDeserializer, IoRead, Read, SliceRead, StrRead};
+use serde_yaml::Deserializer as YamlDeserializer;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::io;
+use std::rc::Rc;
+use std::sync::Arc;
+use yaml_rust::parser::{
+    Event as YamlEvent, EventReceiver as YamlEventReceiver, Parser as YamlParser,
+};
+use yaml_rust::scanner::TScalarStyle;
+
+/// Deserialization error, with the location of the value which failed to deserialize.
+#[derive(Debug, Fail)]
+pub struct Error {
+    pointer: String,
+    limit: Option<Limit>,
+    #[cause]
+    inner: ParseError,
+}
+
+/// Error reported by the format-specific deserializer.
+#[derive(Debug, Fail)]
+pub enum ParseError {
+    #[fail(display = "{}", _0)]
+    Json(#[cause] serde_json::error::Error),
+    #[fail(display = "{}", _0)]
+    Yaml(#[cause] serde_yaml::Error),
+}
+
+impl Error {
+    /// JSON Pointer (RFC 6901) of the value that failed to deserialize.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// Line of the input where the error occurred, or 0 if not known.
+    pub fn line(&self) -> usize {
+        match self.inner {
+            ParseError::Json(ref err) => err.line(),
+            ParseError::Yaml(ref err) => err.location().map_or(0, |loc| loc.line()),
+        }
+    }
+
+    /// Column of the input where the error occurred, or 0 if not known.
+    pub fn column(&self) -> usize {
+        match self.inner {
+            ParseError::Json(ref err) => err.column(),
+            ParseError::Yaml(ref err) => err.location().map_or(0, |loc| loc.column()),
+        }
+    }
+
+    /// Limit exceeded by the input, if that is what failed the deserialization.
+    pub fn limit(&self) -> Option<Limit> {
+        self.limit
+    }
+
+    pub fn inner(&self) -> &ParseError {
+        &self.inner
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            // YAML errors already start with the path of the value (unless it is the root one)
+            ParseError::Yaml(_) => write!(f, "{}", self.inner),
+            _ if self.pointer.is_empty() => write!(f, "{}", self.inner),
+            _ => write!(f, "{}: {}", self.pointer, self.inner),
+        }
+    }
+}
+
+/// Deserialization options.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Record problems (type mismatches, unknown fields) as diagnostics and skip the offending
+    /// values instead of failing on the first one. Skipped fields are left at their defaults
+    /// (`None` for the optional ones), skipped list items and map entries are left out.
+    pub recover: bool,
+    /// What to do with fields not known to the object being deserialized.
+    pub unknown_fields: UnknownFields,
+    /// Types to create for the `Discriminated` fields.
+    pub types: Arc<TypeRegistry>,
+    /// Bounds on the input, for the untrusted documents. Exceeding any of them fails the
+    /// deserialization, even in the recovery mode.
+    pub limits: Limits,
+}
+
+/// Bounds on the input. All of them are unbounded by default.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Nesting depth of the values; fields of the top-level object are at depth 1.
+    pub max_depth: Option<usize>,
+    /// Number of values in the whole document (not counting the top-level object), including nulls.
+    pub max_nodes: Option<usize>,
+    /// Length of the strings in bytes, including the field names.
+    pub max_string_length: Option<usize>,
+    pub max_list_length: Option<usize>,
+    /// Number of fields of a single object.
+    pub max_map_size: Option<usize>,
+}
+
+impl Limits {
+    pub fn get(&self, limit: Limit) -> Option<usize> {
+        match limit {
+            Limit::Depth => self.max_depth,
+            Limit::Nodes => self.max_nodes,
+            Limit::StringLength => self.max_string_length,
+            Limit::ListLength => self.max_list_length,
+            Limit::MapSize => self.max_map_size,
+        }
+    }
+}
+
+/// Limit on the input, see `Limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    Nodes,
+    StringLength,
+    ListLength,
+    MapSize,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Depth => "nesting depth",
+            Limit::Nodes => "node count",
+            Limit::StringLength => "string length",
+            Limit::ListLength => "list length",
+            Limit::MapSize => "map size",
+        })
+    }
+}
+
+/// Policy for fields not known to the object being deserialized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnknownFields {
+    /// Skip unknown fields silently.
+    Ignore,
+    /// Skip unknown fields, recording a diagnostic for each of them.
+    Warn,
+    /// Fail on unknown fields (or record a diagnostic, in recovery mode).
+    Deny,
+}
+
+impl Default for UnknownFields {
+    fn default() -> Self {
+        UnknownFields::Ignore
+    }
+}
+
+/// Problem recovered from during deserialization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// JSON Pointer (RFC 6901) of the offending value.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.pointer, self.message)
+    }
+}
+
+/// Position in the input document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the input.
+    pub offset: usize,
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column number in bytes, starting from 1.
+    pub column: usize,
+}
+
+/// Range of the input document, `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+/// Where the value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeSpan {
+    /// Span of the key, for the values of the object fields.
+    pub key: Option<Span>,
+    pub value: Span,
+}
+
+/// Spans of the deserialized values, by JSON Pointer (RFC 6901), see `read_json_with_spans`.
+#[derive(Debug, Clone, Default)]
+pub struct SpanMap {
+    nodes: BTreeMap<String, NodeSpan>,
+}
+
+impl SpanMap {
+    /// Span of the value at the given pointer.
+    pub fn get(&self, pointer: &str) -> Option<&NodeSpan> {
+        self.nodes.get(pointer)
+    }
+
+    /// Span of the value at the given pointer or, if it has none, of its closest parent which
+    /// has one.
+    pub fn find(&self, pointer: &str) -> Option<&NodeSpan> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(span) = self.nodes.get(pointer) {
+                return Some(span);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &NodeSpan)> {
+        self.nodes
+            .iter()
+            .map(|(pointer, span)| (pointer.as_str(), span))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+// Location tracking
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// State shared by all visitors of a single deserialization run.
+///
+/// Visitors push a path segment before deserializing a nested value and pop it once the value is
+/// successfully deserialized. On error, the stack is left as is, so it points to the failed value.
+struct Context {
+    options: Options,
+    path: RefCell<Vec<Segment>>,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    spans: Option<SpanRecorder>,
+    /// Set while the buffered values are replayed. These were already counted and recorded when
+    /// buffered.
+    replaying: Cell<bool>,
+    /// Set once the current value is rejected as a whole, so the field created for it could be
+    /// discarded when the value is skipped.
+    rejected: Cell<bool>,
+    nodes: Cell<usize>,
+    exceeded: Cell<Option<Limit>>,
+    /// Read string primitives from the source text of the scalars (YAML, where `1.0` or `yes` are
+    /// valid strings, too). Buffered values are parsed by the time they are replayed, so their
+    /// source text is looked up here, by the pointer of the value.
+    scalar_texts: Option<HashMap<String, String>>,
+}
+
+impl Context {
+    fn new(options: &Options) -> Self {
+        Context {
+            options: options.clone(),
+            path: RefCell::new(Vec::new()),
+            diagnostics: RefCell::new(Vec::new()),
+            spans: None,
+            replaying: Cell::new(false),
+            rejected: Cell::new(false),
+            nodes: Cell::new(0),
+            exceeded: Cell::new(None),
+            scalar_texts: None,
+        }
+    }
+
+    fn enter(&self, segment: Segment) {
+        self.path.borrow_mut().push(segment);
+    }
+
+    fn leave(&self) -> Option<Segment> {
+        self.path.borrow_mut().pop()
+    }
+
+    fn pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in self.path.borrow().iter() {
+            pointer.push('/');
+            match segment {
+                Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+                Segment::Index(index) => pointer.push_str(&index.to_string()),
+            }
+        }
+        pointer
+    }
+
+    /// Source text of the current value, if it is a plain scalar.
+    fn scalar_text(&self) -> Option<&str> {
+        let texts = self.scalar_texts.as_ref()?;
+        texts.get(&self.pointer()).map(String::as_str)
+    }
+
+    /// Number of bytes consumed by the deserializer so far, if spans are recorded.
+    fn offset(&self) -> usize {
+        self.spans.as_ref().map_or(0, |spans| spans.offset.get())
+    }
+
+    /// Record the key of the current value, which started at `start` and ends at the current
+    /// offset.
+    fn record_key(&self, start: usize) {
+        if let Some(ref spans) = self.spans {
+            if !self.replaying.get() {
+                let end = spans.offset.get();
+                spans.keys.borrow_mut().insert(self.pointer(), (start, end));
+            }
+        }
+    }
+
+    /// Record the current value, which started at `start` and ends at the current offset.
+    fn record_value(&self, start: usize) {
+        if let Some(ref spans) = self.spans {
+            if !self.replaying.get() {
+                let end = spans.offset.get();
+                spans.values.borrow_mut().push((self.pointer(), start, end));
+            }
+        }
+    }
+
+    fn set_replaying(&self, replaying: bool) -> bool {
+        self.replaying.replace(replaying)
+    }
+
+    /// Fail if the value exceeds the limit. Limits are not subject to the recovery mode.
+    fn check_limit<E: de::Error>(&self, limit: Limit, value: usize) -> Result<(), E> {
+        match self.options.limits.get(limit) {
+            Some(max) if value > max => {
+                self.exceeded.set(Some(limit));
+                Err(E::custom(format!("{} limit of {} exceeded", limit, max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Count the current value towards the node limit, checking its depth.
+    fn count_node<E: de::Error>(&self) -> Result<(), E> {
+        if self.replaying.get() {
+            return Ok(());
+        }
+        self.check_limit(Limit::Depth, self.path.borrow().len())?;
+        self.nodes.set(self.nodes.get() + 1);
+        self.check_limit(Limit::Nodes, self.nodes.get())
+    }
+
+    /// Record a diagnostic for the current value.
+    fn warn(&self, message: String) {
+        self.diagnostics.borrow_mut().push(Diagnostic {
+            pointer: self.pointer(),
+            message,
+        });
+    }
+
+    /// Report a problem with the current value. In recovery mode, the problem is recorded and the
+    /// caller is expected to skip the value; otherwise, it is returned as an error.
+    fn report<E: de::Error>(&self, message: String) -> Result<(), E> {
+        if self.options.recover {
+            self.warn(message);
+            Ok(())
+        } else {
+            Err(E::custom(message))
+        }
+    }
 
-struct ObjectVisitor<'a>(&'a mut Object);
+    /// Report a problem with the current value as a whole (rather than with its parts).
+    fn reject<E: de::Error>(&self, message: String) -> Result<(), E> {
+        self.rejected.set(true);
+        self.report(message)
+    }
+
+    /// Deserialize the value of a field or list item, tracking its rejection separately from the
+    /// value containing it.
+    fn nested<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        let outer = self.rejected.replace(false);
+        let value = f()?;
+        self.rejected.set(outer);
+        Ok(value)
+    }
+
+    fn invalid_type<E: de::Error>(
+        &self,
+        unexpected: Unexpected,
+        exp: &dyn Expected,
+    ) -> Result<(), E> {
+        self.reject(format!("invalid type: {}, expected {}", unexpected, exp))
+    }
+
+    /// Report reflection error, using the serde wording for the errors serde has one.
+    fn reflection_error<E: de::Error>(&self, err: ReflectionError) -> Result<(), E> {
+        match err {
+            ReflectionError::UnknownVariant { value, expected } => {
+                let err = <de::value::Error as de::Error>::unknown_variant(&value, expected);
+                self.reject(err.to_string())
+            }
+            ReflectionError::MissingField { name } => {
+                let err = <de::value::Error as de::Error>::missing_field(name);
+                self.reject(err.to_string())
+            }
+            err => self.reject(err.to_string()),
+        }
+    }
+
+    fn invalid_value<E: de::Error>(
+        &self,
+        unexpected: Unexpected,
+        exp: &dyn Expected,
+    ) -> Result<(), E> {
+        self.reject(format!("invalid value: {}, expected {}", unexpected, exp))
+    }
+}
+
+/// Offsets of the keys and values, as seen by the visitors. Offsets are only approximate (they
+/// might be before the separators or after the look-ahead byte) and are resolved against the
+/// input in `SpanRecorder::finish`.
+struct SpanRecorder {
+    /// Shared with the `TrackedRead` the deserializer reads from.
+    offset: Rc<Cell<usize>>,
+    keys: RefCell<HashMap<String, (usize, usize)>>,
+    values: RefCell<Vec<(String, usize, usize)>>,
+}
+
+impl SpanRecorder {
+    fn new(offset: Rc<Cell<usize>>) -> Self {
+        SpanRecorder {
+            offset,
+            keys: RefCell::new(HashMap::new()),
+            values: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn finish(self, input: &[u8]) -> SpanMap {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            input
+                .iter()
+                .enumerate()
+                .filter(|(_, ch)| **ch == b'\n')
+                .map(|(pos, _)| pos + 1),
+        );
+        let location = |offset: usize| {
+            let line = match line_starts.binary_search(&offset) {
+                Ok(line) => line,
+                Err(line) => line - 1,
+            };
+            Location {
+                offset,
+                line: line + 1,
+                column: offset - line_starts[line] + 1,
+            }
+        };
+        let span = |start: usize, end: usize| Span {
+            start: location(start),
+            end: location(end),
+        };
+
+        let keys = self.keys.into_inner();
+        let mut nodes = BTreeMap::new();
+        for (pointer, start, end) in self.values.into_inner() {
+            // Deserializer might have peeked the first byte of the value already
+            let start = skip_separators(input, start.saturating_sub(1));
+            // ...or the byte after the number
+            let end = match input.get(start) {
+                Some(b'-') | Some(b'0'..=b'9') => {
+                    start
+                        + input[start..]
+                            .iter()
+                            .take_while(|ch| b"+-.eE".contains(ch) || ch.is_ascii_digit())
+                            .count()
+                }
+                _ => end,
+            };
+            let key = keys
+                .get(&pointer)
+                .map(|&(start, end)| span(skip_separators(input, start), end));
+            let value = span(start, end);
+            nodes.insert(pointer, NodeSpan { key, value });
+        }
+        SpanMap { nodes }
+    }
+}
+
+/// Skip whitespace and the separators preceding the key or value.
+fn skip_separators(input: &[u8], mut offset: usize) -> usize {
+    while offset < input.len() && b" \t\r\n,:".contains(&input[offset]) {
+        offset += 1;
+    }
+    offset
+}
 
-impl<'a, 'de> Visitor<'de> for ObjectVisitor<'a> {
+/// Input which keeps track of the bytes consumed by the deserializer. `serde_json` reads from
+/// `io::Read` a byte at a time, so the offset is always up to date.
+struct TrackedRead<'i> {
+    input: &'i [u8],
+    offset: Rc<Cell<usize>>,
+}
+
+impl<'i> io::Read for TrackedRead<'i> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let offset = self.offset.get();
+        let len = buf.len().min(self.input.len() - offset);
+        buf[..len].copy_from_slice(&self.input[offset..offset + len]);
+        self.offset.set(offset + len);
+        Ok(len)
+    }
+}
+
+// Visitors
+
+/// Implements `Visitor` methods for all kinds of values not accepted by the visitor. Each of them
+/// reports the type mismatch to the context (`self.1`), skipping the value if the context is in
+/// recovery mode.
+macro_rules! reject_values {
+    ($($method:ident),*) => {
+        $(reject_values!(@ $method);)*
+    };
+    (@ bool) => {
+        fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Bool(v), &self)
+        }
+    };
+    (@ number) => {
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Signed(v), &self)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Unsigned(v), &self)
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Float(v), &self)
+        }
+    };
+    (@ str) => {
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Str(v), &self)
+        }
+    };
+    (@ unit) => {
+        fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+            self.1.invalid_type(Unexpected::Unit, &self)
+        }
+    };
+    (@ seq) => {
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            self.1.invalid_type(Unexpected::Seq, &self)?;
+            while let Some(IgnoredAny) = seq.next_element()? {}
+            Ok(())
+        }
+    };
+    (@ map) => {
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+            self.1.invalid_type(Unexpected::Map, &self)?;
+            while let Some((IgnoredAny, IgnoredAny)) = map.next_entry()? {}
+            Ok(())
+        }
+    };
+}
+
+struct ObjectVisitor<'a, 'c>(&'a mut dyn Object, &'c Context);
+
+impl<'a, 'c, 'de> Visitor<'de> for ObjectVisitor<'a, 'c> {
     type Value = ();
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "object")
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    fn visit_map<A>(self, map: A) -> Result<(), A::Error>
     where
         A: MapAccess<'de>,
     {
-        while let Some(field_name) = map.next_key::<&str>()? {
-            let field = self.0.create(field_name);
-            match field {
-                Ok(FieldMutReflection::Primitive(primitive)) => {
-                    map.next_value_seed(VisitorSeed(PrimitiveVisitor(primitive)))?;
-                }
-                Ok(FieldMutReflection::Object(object)) => {
-                    map.next_value_seed(VisitorSeed(ObjectVisitor(object)))?;
-                }
-                Ok(FieldMutReflection::List(list)) => {
-                    map.next_value_seed(VisitorSeed(ListVisitor(list)))?;
-                }
-                Ok(FieldMutReflection::Any(value)) => {
-                    *value = map.next_value::<serde_json::Value>()?;
+        self.visit_fields(map, Vec::new())
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+        match self.0.select_primitive(PrimitiveValueKind::Bool) {
+            Some(primitive) => PrimitiveVisitor(primitive, self.1).visit_bool(v),
+            None => self.1.invalid_type(Unexpected::Bool(v), &self),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+        match self.0.select_primitive(PrimitiveValueKind::I64) {
+            Some(primitive) => PrimitiveVisitor(primitive, self.1).visit_i64(v),
+            None => self.1.invalid_type(Unexpected::Signed(v), &self),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+        match self.0.select_primitive(PrimitiveValueKind::U64) {
+            Some(primitive) => PrimitiveVisitor(primitive, self.1).visit_u64(v),
+            None => self.1.invalid_type(Unexpected::Unsigned(v), &self),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+        match self.0.select_primitive(PrimitiveValueKind::F64) {
+            Some(primitive) => PrimitiveVisitor(primitive, self.1).visit_f64(v),
+            None => self.1.invalid_type(Unexpected::Float(v), &self),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+        match self.0.select_primitive(PrimitiveValueKind::String) {
+            Some(primitive) => PrimitiveVisitor(primitive, self.1).visit_str(v),
+            None => self.1.invalid_type(Unexpected::Str(v), &self),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        match self.0.select_list() {
+            Some(list) => ListVisitor(list, self.1).visit_seq(seq),
+            None => {
+                self.1.invalid_type(Unexpected::Seq, &self)?;
+                while let Some(IgnoredAny) = seq.next_element()? {}
+                Ok(())
+            }
+        }
+    }
+
+    reject_values!(unit);
+}
+
+impl<'a, 'c> ObjectVisitor<'a, 'c> {
+    /// Deserialize fields of the object. `buffered` fields, which came before the object was
+    /// created, are deserialized right after the field which selects the variant (the first one,
+    /// unless the object is a union), so that field could switch the variant first.
+    fn visit_fields<'de, A>(
+        mut self,
+        mut map: A,
+        mut buffered: Vec<(String, serde_json::Value)>,
+    ) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let ctx = self.1;
+        let mut seen = Vec::new();
+        let mut size = buffered.len();
+        // Fields also get buffered until the variant of the untagged union is selected
+        let mut selected = false;
+        let mut replay = false;
+        let mut key_start = ctx.offset();
+        while let Some(FieldName(field_name)) = map.next_key()? {
+            let field_name: &str = &field_name;
+            size += 1;
+            ctx.check_limit(Limit::MapSize, size)?;
+            ctx.check_limit(Limit::StringLength, field_name.len())?;
+            if !selected {
+                let mut field_names: Vec<&str> = buffered.iter().map(|(k, _)| k.as_str()).collect();
+                field_names.push(field_name);
+                selected = self.0.select_variant(&field_names);
+                replay = selected;
+            }
+            ctx.enter(Segment::Key(field_name.to_owned()));
+            ctx.record_key(key_start);
+            if !selected {
+                buffered.push((field_name.to_owned(), map.next_value_seed(BufferSeed(ctx))?));
+                ctx.leave();
+                key_start = ctx.offset();
+                continue;
+            }
+            map.next_value_seed(FieldSeed(&mut *self.0, field_name, ctx))?;
+            if let Some(Segment::Key(key)) = ctx.leave() {
+                seen.push(key);
+            }
+            if replay {
+                replay = false;
+                self.replay(&mut buffered, &mut seen)?;
+            }
+            key_start = ctx.offset();
+        }
+        if !selected {
+            let field_names: Vec<&str> = buffered.iter().map(|(k, _)| k.as_str()).collect();
+            if let Err(err) = self.0.select_default_variant(&field_names) {
+                // No variant to deserialize the fields into
+                return ctx.reflection_error(err);
+            }
+            self.replay(&mut buffered, &mut seen)?;
+        }
+
+        // Checking only once all fields are set, as object could change its shape while being
+        // deserialized (for example, untagged unions).
+        let seen: Vec<&str> = seen.iter().map(String::as_str).collect();
+        for name in self.0.missing_fields(&seen) {
+            ctx.report(format!("missing field `{}`", name))?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize fields buffered until the object (or the variant of the untagged union) was
+    /// selected.
+    fn replay<E: de::Error>(
+        &mut self,
+        buffered: &mut Vec<(String, serde_json::Value)>,
+        seen: &mut Vec<String>,
+    ) -> Result<(), E> {
+        let ctx = self.1;
+        let replaying = ctx.set_replaying(true);
+        for (field_name, value) in buffered.drain(..) {
+            ctx.enter(Segment::Key(field_name.clone()));
+            FieldSeed(&mut *self.0, &field_name, ctx)
+                .deserialize(value)
+                .map_err(E::custom)?;
+            if let Some(Segment::Key(key)) = ctx.leave() {
+                seen.push(key);
+            }
+        }
+        ctx.set_replaying(replaying);
+        Ok(())
+    }
+}
+
+/// Seed for the fields buffered until the object could be created. Buffered values go through
+/// the same visitors as the others, so they are subject to the limits and get their spans recorded.
+struct BufferSeed<'c>(&'c Context);
+
+impl<'c, 'de> DeserializeSeed<'de> for BufferSeed<'c> {
+    type Value = serde_json::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<serde_json::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ctx = self.0;
+        let start = ctx.offset();
+        ctx.count_node()?;
+        let value = deserializer.deserialize_option(self)?;
+        ctx.record_value(start);
+        Ok(value)
+    }
+}
+
+impl<'c, 'de> Visitor<'de> for BufferSeed<'c> {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<serde_json::Value, E> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<serde_json::Value, E> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<serde_json::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut value = serde_json::Value::Null;
+        deserialize_field(FieldMutReflection::Object(&mut value), self.0, deserializer)?;
+        Ok(value)
+    }
+}
+
+/// Name of the object field, borrowed from the input if deserializer allows it. Some
+/// deserializers (like the ones reading from `io::Read`) only give transient keys.
+struct FieldName<'de>(Cow<'de, str>);
+
+impl<'de> Deserialize<'de> for FieldName<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(FieldNameVisitor)
+    }
+}
+
+struct FieldNameVisitor;
+
+impl<'de> Visitor<'de> for FieldNameVisitor {
+    type Value = FieldName<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "field name")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(FieldName(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(FieldName(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(FieldName(Cow::Owned(v)))
+    }
+}
+
+/// Seed for the value of the object field. Field is only created once value is known to be
+/// non-null; `null` values are set via `Object::set_null`.
+struct FieldSeed<'a, 'n, 'c>(&'a mut dyn Object, &'n str, &'c Context);
+
+impl<'a, 'n, 'c> FieldSeed<'a, 'n, 'c> {
+    fn unknown_field<E: de::Error>(&self) -> Result<(), E> {
+        let ctx = self.2;
+        if ctx.options.unknown_fields != UnknownFields::Ignore {
+            let expected = self.0.field_names();
+            let message = <de::value::Error as de::Error>::unknown_field(self.1, expected);
+            if ctx.options.unknown_fields == UnknownFields::Deny {
+                ctx.report::<E>(message.to_string())?;
+            } else {
+                ctx.warn(message.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'n, 'c, 'de> DeserializeSeed<'de> for FieldSeed<'a, 'n, 'c> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ctx = self.2;
+        let start = ctx.offset();
+        ctx.count_node()?;
+        ctx.nested(|| deserializer.deserialize_option(self))?;
+        ctx.record_value(start);
+        Ok(())
+    }
+}
+
+impl<'a, 'n, 'c, 'de> Visitor<'de> for FieldSeed<'a, 'n, 'c> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value")
+    }
+
+    fn visit_none<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        match self.0.set_null(self.1) {
+            Ok(()) => Ok(()),
+            Err(ReflectionError::InvalidField { .. }) => self.unknown_field(),
+            Err(err) => self.2.reflection_error(err),
+        }
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ctx = self.2;
+        match self.0.create(self.1) {
+            Ok(field) => {
+                deserialize_field(field, ctx, deserializer)?;
+                if ctx.rejected.get() {
+                    // Skipped values leave the field as it was
+                    self.0.discard(self.1);
                 }
-                Err(_) => {
-                    // Ignoring unknown fields
-                    map.next_value::<serde::de::IgnoredAny>()?;
+                Ok(())
+            }
+            Err(ReflectionError::InvalidField { .. }) => {
+                self.unknown_field()?;
+                IgnoredAny::deserialize(deserializer)?;
+                Ok(())
+            }
+            Err(err) => {
+                ctx.reflection_error(err)?;
+                IgnoredAny::deserialize(deserializer)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Visitor for the objects of the type selected by the discriminator field. Fields which came
+/// before the discriminator are buffered and replayed into the object once it is created.
+struct PolymorphicVisitor<'a, 'c>(&'a mut dyn Polymorphic, &'c Context);
+
+impl<'a, 'c, 'de> Visitor<'de> for PolymorphicVisitor<'a, 'c> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let ctx = self.1;
+        let types = &ctx.options.types;
+        let discriminator = types.discriminator();
+        let mut buffered = Vec::new();
+        let mut key_start = ctx.offset();
+        while let Some(FieldName(field_name)) = map.next_key()? {
+            let field_name: &str = &field_name;
+            ctx.check_limit(Limit::MapSize, buffered.len() + 1)?;
+            ctx.check_limit(Limit::StringLength, field_name.len())?;
+            ctx.enter(Segment::Key(field_name.to_owned()));
+            ctx.record_key(key_start);
+            if field_name != discriminator {
+                buffered.push((field_name.to_owned(), map.next_value_seed(BufferSeed(ctx))?));
+                ctx.leave();
+                key_start = ctx.offset();
+                continue;
+            }
 
-                    // Alternatively, raise an error
-                    // return Err(A::Error::unknown_field(field_name, &[]))
+            // Discriminator of the wrong type is rejected as any other value of the wrong type
+            let type_name = match String::deserialize(map.next_value_seed(BufferSeed(ctx))?) {
+                Ok(type_name) => type_name,
+                Err(err) => {
+                    ctx.reject(err.to_string())?;
+                    ctx.leave();
+                    while let Some((IgnoredAny, IgnoredAny)) = map.next_entry()? {}
+                    return Ok(());
                 }
+            };
+            ctx.check_limit(Limit::StringLength, type_name.len())?;
+            let object = match types.create(&type_name) {
+                Some(object) => object,
+                None => {
+                    ctx.report(unknown_type(&type_name, &types.type_names()))?;
+                    ctx.leave();
+                    while let Some((IgnoredAny, IgnoredAny)) = map.next_entry()? {}
+                    return Ok(());
+                }
+            };
+            ctx.leave();
+            let object = self.0.set_object(discriminator, &type_name, object);
+            if object.knows_fields(&[discriminator]) {
+                buffered.push((
+                    discriminator.to_owned(),
+                    serde_json::Value::String(type_name),
+                ));
             }
+            return ObjectVisitor(object, ctx).visit_fields(map, buffered);
         }
-        Ok(())
+        ctx.report(format!("missing field `{}`", discriminator))
     }
+
+    reject_values!(bool, number, str, unit, seq);
 }
 
-struct ListVisitor<'a>(&'a mut List);
+fn unknown_type(type_name: &str, expected: &[&str]) -> String {
+    if expected.is_empty() {
+        format!("unknown type `{}`, there are no types", type_name)
+    } else {
+        let expected: Vec<String> = expected.iter().map(|name| format!("`{}`", name)).collect();
+        format!(
+            "unknown type `{}`, expected one of {}",
+            type_name,
+            expected.join(", ")
+        )
+    }
+}
+
+struct ListVisitor<'a, 'c>(&'a mut dyn List, &'c Context);
 
-impl<'a, 'de> Visitor<'de> for ListVisitor<'a> {
+impl<'a, 'c, 'de> Visitor<'de> for ListVisitor<'a, 'c> {
     type Value = ();
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -60,41 +997,112 @@ impl<'a, 'de> Visitor<'de> for ListVisitor<'a> {
     where
         A: SeqAccess<'de>,
     {
-        while let Some(()) = seq.next_element_seed(ListEntrySeed(self.0))? {}
+        let mut index = 0;
+        while let Some(()) = seq.next_element_seed(ListEntrySeed(self.0, self.1, index))? {
+            index += 1;
+        }
 
         Ok(())
     }
+
+    reject_values!(bool, number, str, unit, map);
 }
 
-struct ListEntrySeed<'a>(&'a mut List);
+struct ListEntrySeed<'a, 'c>(&'a mut dyn List, &'c Context, usize);
 
-impl<'a, 'de> DeserializeSeed<'de> for ListEntrySeed<'a> {
+impl<'a, 'c, 'de> DeserializeSeed<'de> for ListEntrySeed<'a, 'c> {
     type Value = ();
     fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
     where
         D: Deserializer<'de>,
     {
-        match self.0.push() {
-            FieldMutReflection::Primitive(primitive) => {
-                deserializer.deserialize_any(PrimitiveVisitor(primitive))?;
-            }
-            FieldMutReflection::Object(object) => {
-                deserializer.deserialize_any(ObjectVisitor(object))?;
-            }
-            FieldMutReflection::List(list) => {
-                deserializer.deserialize_any(ListVisitor(list))?;
+        let ctx = self.1;
+        ctx.check_limit(Limit::ListLength, self.2 + 1)?;
+        ctx.enter(Segment::Index(self.2));
+        let start = ctx.offset();
+        ctx.count_node()?;
+        ctx.nested(|| deserializer.deserialize_option(self))?;
+        ctx.record_value(start);
+        ctx.leave();
+        Ok(())
+    }
+}
+
+impl<'a, 'c, 'de> Visitor<'de> for ListEntrySeed<'a, 'c> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value")
+    }
+
+    fn visit_none<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        match self.0.push_null() {
+            Ok(()) => Ok(()),
+            Err(err) => self.1.report(err.to_string()),
+        }
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ctx = self.1;
+        deserialize_field(self.0.push(), ctx, deserializer)?;
+        if ctx.rejected.get() {
+            self.0.discard_last();
+        }
+        Ok(())
+    }
+}
+
+/// Deserialize value into the given field
+fn deserialize_field<'de, D>(
+    field: FieldMutReflection,
+    ctx: &Context,
+    deserializer: D,
+) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match field {
+        FieldMutReflection::Primitive(primitive)
+            if ctx.scalar_texts.is_some() && primitive.kind() == PrimitiveValueKind::String =>
+        {
+            if !ctx.replaying.get() {
+                return deserializer.deserialize_str(PrimitiveVisitor(primitive, ctx));
             }
-            FieldMutReflection::Any(value) => {
-                *value = serde_json::Value::deserialize(deserializer)?;
+            match ctx.scalar_text() {
+                Some(text) => PrimitiveVisitor(primitive, ctx).visit_str(text),
+                None => deserializer.deserialize_any(PrimitiveVisitor(primitive, ctx)),
             }
         }
-        Ok(())
+        FieldMutReflection::Primitive(primitive) => {
+            deserializer.deserialize_any(PrimitiveVisitor(primitive, ctx))
+        }
+        FieldMutReflection::Object(object) => {
+            deserializer.deserialize_any(ObjectVisitor(object, ctx))
+        }
+        FieldMutReflection::List(list) => deserializer.deserialize_any(ListVisitor(list, ctx)),
+        FieldMutReflection::Polymorphic(polymorphic) => {
+            deserializer.deserialize_any(PolymorphicVisitor(polymorphic, ctx))
+        }
+        FieldMutReflection::Any(value) => deserializer.deserialize_any(ObjectVisitor(value, ctx)),
     }
 }
 
-struct PrimitiveVisitor<'a>(&'a mut Primitive);
+struct PrimitiveVisitor<'a, 'c>(&'a mut dyn Primitive, &'c Context);
 
-impl<'a, 'de> Visitor<'de> for PrimitiveVisitor<'a> {
+impl<'a, 'c, 'de> Visitor<'de> for PrimitiveVisitor<'a, 'c> {
     type Value = ();
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -105,9 +1113,28 @@ impl<'a, 'de> Visitor<'de> for PrimitiveVisitor<'a> {
     where
         E: de::Error,
     {
-        self.0
-            .set(PrimitiveValue::Bool(v))
-            .map_err(|_| Error::invalid_type(Unexpected::Bool(v), &self))
+        self.set(PrimitiveValue::Bool(v), Unexpected::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.set(PrimitiveValue::I64(v), Unexpected::Signed(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.set(PrimitiveValue::U64(v), Unexpected::Unsigned(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.set(PrimitiveValue::F64(v), Unexpected::Float(v))
     }
 
     fn visit_str<E>(self, v: &str) -> Result<(), E>
@@ -121,27 +1148,340 @@ impl<'a, 'de> Visitor<'de> for PrimitiveVisitor<'a> {
     where
         E: de::Error,
     {
-        self.0
-            .set(PrimitiveValue::String(v))
-            .map_err(|_| Error::invalid_type(Unexpected::Other("string"), &self))
+        self.1.check_limit(Limit::StringLength, v.len())?;
+        self.set(PrimitiveValue::String(v), Unexpected::Other("string"))
+    }
+
+    reject_values!(unit, seq, map);
+}
+
+impl<'a, 'c> PrimitiveVisitor<'a, 'c> {
+    fn set<E: de::Error>(self, value: PrimitiveValue, unexpected: Unexpected) -> Result<(), E> {
+        match self.0.set(value) {
+            Ok(()) => Ok(()),
+            Err(ReflectionError::OutOfRange { expected, .. }) => {
+                self.1.invalid_value(unexpected, &expected)
+            }
+            Err(err @ ReflectionError::UnknownVariant { .. }) => self.1.reflection_error(err),
+            Err(_) => self.1.invalid_type(unexpected, &self),
+        }
     }
 }
 
-struct VisitorSeed<V: for<'de> Visitor<'de>>(V);
+pub fn read_json(input: &str, object: &mut dyn Object) -> Result<(), Error> {
+    read_json_with(input, object, &Options::default())?;
+    Ok(())
+}
 
-impl<'de, V: for<'a> Visitor<'a>> DeserializeSeed<'de> for VisitorSeed<V> {
-    type Value = <V as Visitor<'de>>::Value;
+/// Deserialize JSON input into the given object. Returns problems recovered from, if the recovery
+/// mode is enabled in the options.
+pub fn read_json_with(
+    input: &str,
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<Vec<Diagnostic>, Error> {
+    let ctx = Context::new(options);
+    read(StrRead::new(input), object, &ctx)?;
+    Ok(ctx.diagnostics.into_inner())
+}
 
-    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_any(self.0)
+/// Deserialize JSON input into the given object, recording where each of the values came from.
+/// Returns problems recovered from, if the recovery mode is enabled in the options, and the spans
+/// of the values.
+///
+/// Slower than `read_json_with`, as the input is read a byte at a time.
+pub fn read_json_with_spans(
+    input: &str,
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<(Vec<Diagnostic>, SpanMap), Error> {
+    let offset = Rc::new(Cell::new(0));
+    let mut ctx = Context::new(options);
+    ctx.spans = Some(SpanRecorder::new(offset.clone()));
+    let input = input.as_bytes();
+    read(IoRead::new(TrackedRead { input, offset }), object, &ctx)?;
+    let spans = ctx.spans.take().unwrap().finish(input);
+    Ok((ctx.diagnostics.into_inner(), spans))
+}
+
+/// Deserialize JSON input given as bytes into the given object.
+pub fn read_json_slice(input: &[u8], object: &mut dyn Object) -> Result<(), Error> {
+    read_json_slice_with(input, object, &Options::default())?;
+    Ok(())
+}
+
+/// Deserialize JSON input given as bytes into the given object. Returns problems recovered from,
+/// if the recovery mode is enabled in the options.
+pub fn read_json_slice_with(
+    input: &[u8],
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<Vec<Diagnostic>, Error> {
+    let ctx = Context::new(options);
+    read(SliceRead::new(input), object, &ctx)?;
+    Ok(ctx.diagnostics.into_inner())
+}
+
+/// Deserialize JSON input from the reader into the given object. Reader is not buffered, wrap it
+/// into `io::BufReader` if necessary.
+pub fn read_json_reader<R: io::Read>(reader: R, object: &mut dyn Object) -> Result<(), Error> {
+    read_json_reader_with(reader, object, &Options::default())?;
+    Ok(())
+}
+
+/// Deserialize JSON input from the reader into the given object. Returns problems recovered from,
+/// if the recovery mode is enabled in the options.
+pub fn read_json_reader_with<R: io::Read>(
+    reader: R,
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<Vec<Diagnostic>, Error> {
+    let ctx = Context::new(options);
+    read(IoRead::new(reader), object, &ctx)?;
+    Ok(ctx.diagnostics.into_inner())
+}
+
+fn read<'de, R: Read<'de>>(read: R, object: &mut dyn Object, ctx: &Context) -> Result<(), Error> {
+    let start = ctx.offset();
+    let mut de = JsonDeserializer::new(read);
+    de.deserialize_any(ObjectVisitor(object, ctx))
+        .map_err(|err| Error {
+            pointer: ctx.pointer(),
+            limit: ctx.exceeded.get(),
+            inner: ParseError::Json(err),
+        })?;
+    ctx.record_value(start);
+    Ok(())
+}
+
+/// Deserialize YAML input into the given object. Only a single document is supported.
+pub fn read_yaml(input: &str, object: &mut dyn Object) -> Result<(), Error> {
+    read_yaml_with(input, object, &Options::default())?;
+    Ok(())
+}
+
+/// Deserialize YAML input into the given object. Returns problems recovered from, if the recovery
+/// mode is enabled in the options.
+///
+/// Keys which are not strings in YAML (like `200:` in the OpenAPI responses) are taken as they
+/// are written; aliases are expanded into the copies of the anchored nodes. Same goes for scalars
+/// given for the string fields (`version: 1.0` is read as `"1.0"`), though a mapping or a sequence
+/// given for the string field is an error even in the recovery mode.
+pub fn read_yaml_with(
+    input: &str,
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<Vec<Diagnostic>, Error> {
+    let mut ctx = Context::new(options);
+    ctx.scalar_texts = Some(ScalarTexts::collect(input));
+    YamlDeserializer::from_str(input)
+        .deserialize_any(ObjectVisitor(object, &ctx))
+        .map_err(|err| Error {
+            pointer: ctx.pointer(),
+            limit: ctx.exceeded.get(),
+            inner: ParseError::Yaml(err),
+        })?;
+    Ok(ctx.diagnostics.into_inner())
+}
+
+/// Collects the source text of the plain YAML scalars, by the pointer of the value. Aliases get the
+/// texts of the anchored nodes, as serde_yaml expands them into copies.
+#[derive(Default)]
+struct ScalarTexts {
+    texts: HashMap<String, String>,
+    /// Pointer of the current collection.
+    pointer: String,
+    collections: Vec<Collection>,
+    anchors: HashMap<usize, AnchoredTexts>,
+    /// Anchored collections being read, with the length of their pointers.
+    recording: Vec<(usize, usize, AnchoredTexts)>,
+}
+
+/// Texts of the anchored node, by the pointer relative to the node.
+type AnchoredTexts = Vec<(String, String)>;
+
+enum Collection {
+    Sequence {
+        parent: String,
+        anchor: usize,
+        index: usize,
+    },
+    /// `key` is `None` while the key is expected.
+    Mapping {
+        parent: String,
+        anchor: usize,
+        key: Option<String>,
+    },
+}
+
+impl ScalarTexts {
+    /// Errors are left to the deserializer to report.
+    fn collect(input: &str) -> HashMap<String, String> {
+        let mut texts = ScalarTexts::default();
+        let _ = YamlParser::new(input.chars()).load(&mut texts, false);
+        texts.texts
+    }
+
+    /// Pointer of the next node, `None` for the keys.
+    fn node_pointer(&self) -> Option<String> {
+        let segment = match self.collections.last() {
+            None => return Some(String::new()),
+            Some(Collection::Sequence { index, .. }) => index.to_string(),
+            Some(Collection::Mapping { key: None, .. }) => return None,
+            Some(Collection::Mapping { key: Some(key), .. }) => {
+                key.replace('~', "~0").replace('/', "~1")
+            }
+        };
+        Some(format!("{}/{}", self.pointer, segment))
+    }
+
+    /// Advance the current collection past the node. Keys which are not scalars are taken as
+    /// empty, these are not used by the objects anyway.
+    fn node_done(&mut self, key: Option<&str>) {
+        match self.collections.last_mut() {
+            Some(Collection::Sequence { index, .. }) => *index += 1,
+            Some(Collection::Mapping { key: current, .. }) => {
+                *current = match current {
+                    None => Some(key.unwrap_or_default().to_owned()),
+                    Some(_) => None,
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn record(&mut self, pointer: &str, text: &str) {
+        self.texts.insert(pointer.to_owned(), text.to_owned());
+        for (_, len, texts) in &mut self.recording {
+            texts.push((pointer[*len..].to_owned(), text.to_owned()));
+        }
+    }
+
+    fn start(&mut self, anchor: usize, sequence: bool) {
+        // Keys which are collections get the pointer no value has
+        let pointer = self.node_pointer().unwrap_or_else(|| "?".to_owned());
+        if anchor != 0 {
+            self.recording.push((anchor, pointer.len(), Vec::new()));
+        }
+        let parent = std::mem::replace(&mut self.pointer, pointer);
+        self.collections.push(if sequence {
+            Collection::Sequence {
+                parent,
+                anchor,
+                index: 0,
+            }
+        } else {
+            Collection::Mapping {
+                parent,
+                anchor,
+                key: None,
+            }
+        });
+    }
+
+    fn end(&mut self) {
+        let (parent, anchor) = match self.collections.pop() {
+            Some(Collection::Sequence { parent, anchor, .. })
+            | Some(Collection::Mapping { parent, anchor, .. }) => (parent, anchor),
+            None => return,
+        };
+        if anchor != 0 {
+            if let Some((anchor, _, texts)) = self.recording.pop() {
+                self.anchors.insert(anchor, texts);
+            }
+        }
+        self.pointer = parent;
+        self.node_done(None);
+    }
+}
+
+impl YamlEventReceiver for ScalarTexts {
+    fn on_event(&mut self, event: YamlEvent) {
+        match event {
+            YamlEvent::Scalar(text, style, anchor, tag) => {
+                let plain = style == TScalarStyle::Plain && tag.is_none();
+                if let Some(pointer) = self.node_pointer() {
+                    if plain {
+                        self.record(&pointer, &text);
+                        if anchor != 0 {
+                            self.anchors
+                                .insert(anchor, vec![(String::new(), text.clone())]);
+                        }
+                    }
+                }
+                self.node_done(Some(&text));
+            }
+            YamlEvent::Alias(anchor) => {
+                let texts = self.anchors.get(&anchor).cloned().unwrap_or_default();
+                match self.node_pointer() {
+                    Some(pointer) => {
+                        for (suffix, text) in &texts {
+                            self.record(&format!("{}{}", pointer, suffix), text);
+                        }
+                        self.node_done(None);
+                    }
+                    None => {
+                        let key = texts.iter().find(|(suffix, _)| suffix.is_empty());
+                        self.node_done(key.map(|(_, text)| text.as_str()));
+                    }
+                }
+            }
+            YamlEvent::SequenceStart(anchor) => self.start(anchor, true),
+            YamlEvent::MappingStart(anchor) => self.start(anchor, false),
+            YamlEvent::SequenceEnd | YamlEvent::MappingEnd => self.end(),
+            _ => {}
+        }
     }
 }
 
-pub fn read_json(input: &str, object: &mut Object) -> Result<(), serde_json::error::Error> {
-    let mut de = JsonDeserializer::new(StrRead::new(input));
-    de.deserialize_any(ObjectVisitor(object))?;
+/// Deserialize into the given object from any serde deserializer.
+pub fn deserialize_into<'de, D>(deserializer: D, object: &mut dyn Object) -> Result<(), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_into_with(deserializer, object, &Options::default())?;
     Ok(())
 }
+
+/// Deserialize into the given object from any serde deserializer. Returns problems recovered
+/// from, if the recovery mode is enabled in the options.
+///
+/// Unlike `read_json`, errors do not carry the location of the failed value, as they are
+/// specific to the deserializer.
+pub fn deserialize_into_with<'de, D>(
+    deserializer: D,
+    object: &mut dyn Object,
+    options: &Options,
+) -> Result<Vec<Diagnostic>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    ObjectSeed::new(object, options).deserialize(deserializer)
+}
+
+/// Adapter to deserialize into the given object where serde expects a `DeserializeSeed` (for
+/// example, for a single element of a sequence). Deserializes into problems recovered from, if the
+/// recovery mode is enabled in the options.
+pub struct ObjectSeed<'a, 'o> {
+    object: &'a mut dyn Object,
+    options: &'o Options,
+}
+
+impl<'a, 'o> ObjectSeed<'a, 'o> {
+    pub fn new(object: &'a mut dyn Object, options: &'o Options) -> Self {
+        ObjectSeed { object, options }
+    }
+}
+
+impl<'a, 'o, 'de> DeserializeSeed<'de> for ObjectSeed<'a, 'o> {
+    type Value = Vec<Diagnostic>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Vec<Diagnostic>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ctx = Context::new(self.options);
+        deserializer.deserialize_any(ObjectVisitor(self.object, &ctx))?;
+        Ok(ctx.diagnostics.into_inner())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::reflection::{FieldRefReflection, ListRef, ObjectRef, PrimitiveRef, PrimitiveValueRef};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::io;
+
+struct ObjectSerializer<'a>(&'a dyn ObjectRef);
+
+impl<'a> Serialize for ObjectSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Some(variant) = self.0.variant() {
+            return FieldSerializer(variant).serialize(serializer);
+        }
+        let fields = self.0.fields();
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for (field_name, field) in fields {
+            map.serialize_entry(field_name, &FieldSerializer(field))?;
+        }
+        map.end()
+    }
+}
+
+struct ListSerializer<'a>(&'a dyn ListRef);
+
+impl<'a> Serialize for ListSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for item in self.0.iter() {
+            seq.serialize_element(&FieldSerializer(item))?;
+        }
+        seq.end()
+    }
+}
+
+struct PrimitiveSerializer<'a>(&'a dyn PrimitiveRef);
+
+impl<'a> Serialize for PrimitiveSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0.value() {
+            PrimitiveValueRef::String(v) => serializer.serialize_str(v),
+            PrimitiveValueRef::Bool(v) => serializer.serialize_bool(v),
+            PrimitiveValueRef::I64(v) => serializer.serialize_i64(v),
+            PrimitiveValueRef::U64(v) => serializer.serialize_u64(v),
+            PrimitiveValueRef::F64(v) => serializer.serialize_f64(v),
+        }
+    }
+}
+
+struct FieldSerializer<'a>(FieldRefReflection<'a>);
+
+impl<'a> Serialize for FieldSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            FieldRefReflection::Object(object) => ObjectSerializer(object).serialize(serializer),
+            FieldRefReflection::List(list) => ListSerializer(list).serialize(serializer),
+            FieldRefReflection::Primitive(primitive) => {
+                PrimitiveSerializer(primitive).serialize(serializer)
+            }
+            FieldRefReflection::Any(value) => ObjectSerializer(value).serialize(serializer),
+            FieldRefReflection::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+pub fn write_json(object: &dyn ObjectRef) -> Result<String, serde_json::error::Error> {
+    serde_json::to_string(&ObjectSerializer(object))
+}
+
+pub fn write_json_pretty(object: &dyn ObjectRef) -> Result<String, serde_json::error::Error> {
+    serde_json::to_string_pretty(&ObjectSerializer(object))
+}
+
+/// Write object as a YAML document. Fields are written in the order given by the object, strings
+/// which would read back as other types (like `"200"` or `"yes"`) are quoted.
+pub fn write_yaml<W: io::Write>(
+    object: &dyn ObjectRef,
+    writer: W,
+) -> Result<(), serde_yaml::Error> {
+    serde_yaml::to_writer(writer, &ObjectSerializer(object))
+}
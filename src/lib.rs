@@ -2,15 +2,27 @@
 #[cfg(test)]
 extern crate test;
 
+pub mod dynamic;
 pub mod dyndeser;
+pub mod dynser;
 pub mod openapi;
 pub mod reflection;
+pub mod registry;
 
 #[cfg(test)]
 mod tests {
     use test::Bencher;
 
     static TEST_CASE: &str = include_str!("data/buy_browse_v1_beta_oas3.json");
+    static YAML_TEST_CASE: &str = include_str!("data/buy_browse_v1_beta_oas3.yaml");
+
+    #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+    struct Version {
+        // Explicit marker on a type which is a primitive anyway
+        #[primitive]
+        name: String,
+        stable: bool,
+    }
 
     #[test]
     fn compare_test() {
@@ -22,6 +34,1242 @@ mod tests {
         assert_eq!(serde_openapi, reflection_openapi);
     }
 
+    #[test]
+    fn read_test() {
+        let mut expected = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(TEST_CASE, &mut expected).unwrap();
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json_slice(TEST_CASE.as_bytes(), &mut openapi).unwrap();
+        assert_eq!(expected, openapi);
+
+        // Reader gives transient keys, which could not be borrowed
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json_reader(TEST_CASE.as_bytes(), &mut openapi).unwrap();
+        assert_eq!(expected, openapi);
+
+        // Value gives owned keys
+        let value = serde_json::json!({"name": "dynser", "stable": true});
+        let mut version = Version::default();
+        crate::dyndeser::deserialize_into(value, &mut version).unwrap();
+        assert_eq!("dynser", version.name);
+
+        let input = b"{\"name\": \"dynser\",\n \"stable\": 1}";
+        let mut version = Version::default();
+        let err = crate::dyndeser::read_json_reader(&input[..], &mut version).unwrap_err();
+        assert_eq!("/stable", err.pointer());
+        assert_eq!(2, err.line());
+    }
+
+    #[test]
+    fn yaml_test() {
+        let mut expected = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(TEST_CASE, &mut expected).unwrap();
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_yaml(YAML_TEST_CASE, &mut openapi).unwrap();
+        assert_eq!(expected, openapi);
+
+        let input = r##"
+openapi: 3.0.0
+info:
+  title: Test
+  version: "1"
+  description: |
+    First line
+    Second line
+paths:
+  /items:
+    get:
+      responses:
+        200: &ok
+          description: >
+            Folded
+            text
+        404: *ok
+"##;
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_yaml(input, &mut openapi).unwrap();
+        assert_eq!(
+            Some("First line\nSecond line\n"),
+            openapi.info.description.as_ref().map(String::as_str)
+        );
+        let output: serde_json::Value =
+            serde_json::from_str(&crate::dynser::write_json(&openapi.paths).unwrap()).unwrap();
+        let responses = &output["/items"]["get"]["responses"];
+        assert_eq!("Folded text\n", responses["200"]["description"]);
+        assert_eq!(responses["200"], responses["404"]);
+
+        // Scalars given for strings are taken as written, same as the keys
+        let input = "openapi: 3.0.0\ninfo: {title: yes, version: 1.0}\npaths: {}\n";
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_yaml(input, &mut openapi).unwrap();
+        assert_eq!("yes", openapi.info.title);
+        assert_eq!("1.0", openapi.info.version);
+
+        // Including the ones buffered until the tag selects the variant
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Release {
+            version: String,
+            tags: Vec<String>,
+            stable: bool,
+        }
+
+        #[derive(Debug, PartialEq, dynser_derive::Object)]
+        #[dynser(tag = "kind", rename_all = "snake_case")]
+        enum Package {
+            Release(Release),
+            Draft,
+        }
+
+        impl Default for Package {
+            fn default() -> Self {
+                Package::Draft
+            }
+        }
+
+        let input = "version: &v 1.10\ntags: [*v, 0x1F, true]\nstable: true\nkind: release\n";
+        let mut package = Package::default();
+        crate::dyndeser::read_yaml(input, &mut package).unwrap();
+        assert_eq!(
+            Package::Release(Release {
+                version: "1.10".to_owned(),
+                tags: vec!["1.10".to_owned(), "0x1F".to_owned(), "true".to_owned()],
+                stable: true,
+            }),
+            package
+        );
+
+        let input = "name: dynser\nstable: 1\n";
+        let mut version = Version::default();
+        let err = crate::dyndeser::read_yaml(input, &mut version).unwrap_err();
+        assert_eq!("/stable", err.pointer());
+        assert_eq!(2, err.line());
+        assert_eq!(
+            "stable: invalid type: integer `1`, expected Bool at line 2 column 9",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(TEST_CASE, &mut openapi).unwrap();
+
+        for output in &[
+            crate::dynser::write_json(&openapi).unwrap(),
+            crate::dynser::write_json_pretty(&openapi).unwrap(),
+        ] {
+            let mut reparsed = crate::openapi::OpenApi::default();
+            crate::dyndeser::read_json(output, &mut reparsed).unwrap();
+            assert_eq!(openapi, reparsed);
+
+            let serde_openapi: crate::openapi::OpenApi = serde_json::from_str(output).unwrap();
+            assert_eq!(openapi, serde_openapi);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-flatten"))]
+    fn write_test() {
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(
+            r##"{"openapi": "3.0.0", "info": {"title": "Test", "version": "1", "x-internal": true},
+                "paths": {"/items": {"parameters": [{"$ref": "#/components/parameters/id"}]}}}"##,
+            &mut openapi,
+        )
+        .unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&crate::dynser::write_json(&openapi).unwrap()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "openapi": "3.0.0",
+                "info": {"title": "Test", "version": "1", "x-internal": true},
+                "servers": [],
+                "paths": {"/items": {"servers": [], "parameters": [{"$ref": "#/components/parameters/id"}]}},
+                "security": [],
+                "tags": [],
+            }),
+            output
+        );
+    }
+
+    #[test]
+    fn write_yaml_test() {
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(TEST_CASE, &mut openapi).unwrap();
+
+        let mut output = Vec::new();
+        crate::dynser::write_yaml(&openapi, &mut output).unwrap();
+        let mut reparsed = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_yaml(std::str::from_utf8(&output).unwrap(), &mut reparsed).unwrap();
+        assert_eq!(openapi, reparsed);
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(
+            r#"{"openapi": "3.0.0", "info": {"version": "1.0", "title": "yes", "description": "null"},
+                "paths": {"/items": {"get": {"responses": {"200": {"description": "OK"}}}}}}"#,
+            &mut openapi,
+        )
+        .unwrap();
+        let mut output = Vec::new();
+        crate::dynser::write_yaml(&openapi.info, &mut output).unwrap();
+        assert_eq!(
+            "---\ntitle: \"yes\"\ndescription: \"null\"\nversion: \"1.0\"\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let mut output = Vec::new();
+        let responses = &openapi.paths["/items"].get.as_ref().unwrap().responses;
+        crate::dynser::write_yaml(responses, &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("\"200\":"));
+    }
+
+    #[test]
+    fn error_pointer_test() {
+        let input = r##"{
+  "paths": {
+    "/items": {
+      "get": {
+        "parameters": [{"$ref": "#/a"}, {"$ref": "#/b"}, {"$ref": "#/c"}, {"name": "id", "required": "yes"}]
+      }
+    }
+  }
+}"##;
+        let mut openapi = crate::openapi::OpenApi::default();
+        let err = crate::dyndeser::read_json(input, &mut openapi).unwrap_err();
+        assert_eq!("/paths/~1items/get/parameters/3/required", err.pointer());
+        assert_eq!(5, err.line());
+        assert_eq!(106, err.column());
+        assert_eq!(
+            "/paths/~1items/get/parameters/3/required: \
+             invalid type: string, expected Bool at line 5 column 106",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn spans_test() {
+        let input = r##"{
+  "openapi": "3.0.0",
+  "info": {"title": "Test", "version": "1", "x-count": 42},
+  "paths": {
+    "/items": {
+      "parameters": [{"$ref": "#/a"}, {"name": "id", "in": "query", "required": "yes"}]
+    }
+  }
+}"##;
+        let mut openapi = crate::openapi::OpenApi::default();
+        let options = crate::dyndeser::Options {
+            recover: true,
+            ..Default::default()
+        };
+        let (diagnostics, spans) =
+            crate::dyndeser::read_json_with_spans(input, &mut openapi, &options).unwrap();
+
+        let text = |span: crate::dyndeser::Span| &input[span.start.offset..span.end.offset];
+        let root = spans.get("").unwrap();
+        assert_eq!(None, root.key);
+        assert_eq!(input, text(root.value));
+
+        let title = spans.get("/info/title").unwrap();
+        assert_eq!("\"title\"", text(title.key.unwrap()));
+        assert_eq!("\"Test\"", text(title.value));
+        let key_start = title.key.unwrap().start;
+        assert_eq!((3, 12), (key_start.line, key_start.column));
+
+        let count = spans.get("/info/x-count").unwrap();
+        assert_eq!("42", text(count.value));
+
+        let parameter = spans.get("/paths/~1items/parameters/0").unwrap();
+        assert_eq!(None, parameter.key);
+        assert_eq!("{\"$ref\": \"#/a\"}", text(parameter.value));
+
+        // Diagnostics are located by their pointers
+        let pointer = &diagnostics[0].pointer;
+        assert_eq!("/paths/~1items/parameters/1/required", pointer);
+        let required = spans.find(pointer).unwrap();
+        assert_eq!("\"yes\"", text(required.value));
+        assert_eq!(6, required.value.start.line);
+
+        let mut expected = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json_with(input, &mut expected, &options).unwrap();
+        assert_eq!(expected, openapi);
+    }
+
+    #[test]
+    fn deserializer_test() {
+        use serde::de::DeserializeSeed;
+
+        let input = r#"{"name": "dynser", "stable": true}"#;
+        let mut version = Version::default();
+        let mut de = serde_json::Deserializer::from_str(input);
+        crate::dyndeser::deserialize_into(&mut de, &mut version).unwrap();
+        assert_eq!(
+            Version {
+                name: "dynser".to_owned(),
+                stable: true,
+            },
+            version
+        );
+
+        let input = r#"{"name": 1, "stable": false}"#;
+        let options = crate::dyndeser::Options {
+            recover: true,
+            ..Default::default()
+        };
+        let mut de = serde_json::Deserializer::from_str(input);
+        let diagnostics = crate::dyndeser::ObjectSeed::new(&mut version, &options)
+            .deserialize(&mut de)
+            .unwrap();
+        assert!(!version.stable);
+        let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec!["/name: invalid type: integer `1`, expected String"],
+            messages
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-flatten"))]
+    fn recover_test() {
+        use crate::dyndeser::{Diagnostic, Options, UnknownFields};
+
+        let input = r#"{
+  "openapi": true,
+  "info": {"title": "Test", "description": 5, "version": "1"},
+  "paths": {
+    "/items": {
+      "get": {
+        "tags": ["a", {"b": 1}, "c"],
+        "parameters": [{"name": "id", "required": "yes"}],
+        "responses": []
+      }
+    }
+  },
+  "servers": {"url": "https://example.com"}
+}"#;
+        let mut openapi = crate::openapi::OpenApi::default();
+        let options = Options {
+            recover: true,
+            unknown_fields: UnknownFields::Warn,
+            ..Default::default()
+        };
+        let mut diagnostics =
+            crate::dyndeser::read_json_with(input, &mut openapi, &options).unwrap();
+        diagnostics.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+
+        let diagnostic = |pointer: &str, message: &str| Diagnostic {
+            pointer: pointer.to_owned(),
+            message: message.to_owned(),
+        };
+        assert_eq!(
+            vec![
+                diagnostic(
+                    "/info/description",
+                    "invalid type: integer `5`, expected String"
+                ),
+                diagnostic("/openapi", "invalid type: boolean `true`, expected String"),
+                diagnostic("/paths/~1items/get/parameters/0", "missing field `in`"),
+                diagnostic(
+                    "/paths/~1items/get/parameters/0/required",
+                    "invalid type: string, expected Bool"
+                ),
+                diagnostic(
+                    "/paths/~1items/get/responses",
+                    "invalid type: sequence, expected object"
+                ),
+                diagnostic(
+                    "/paths/~1items/get/tags/1",
+                    "invalid type: map, expected String"
+                ),
+                diagnostic("/servers", "invalid type: map, expected array"),
+            ],
+            diagnostics
+        );
+
+        // Values after the problems are still deserialized, skipped ones are left out
+        assert_eq!("Test", openapi.info.title);
+        assert_eq!(None, openapi.info.description);
+        let get = openapi.paths["/items"].get.as_ref().unwrap();
+        assert_eq!(vec!["a", "c"], get.tags);
+        assert_eq!(1, get.parameters.len());
+
+        // Without recovery, the first problem is an error
+        let mut openapi = crate::openapi::OpenApi::default();
+        let err = crate::dyndeser::read_json(input, &mut openapi).unwrap_err();
+        assert_eq!("/openapi", err.pointer());
+
+        // Unknown fields are reported, too
+        let mut version = Version::default();
+        let diagnostics = crate::dyndeser::read_json_with(
+            r#"{"nmae": "x", "name": "dynser"}"#,
+            &mut version,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![diagnostic(
+                "/nmae",
+                "unknown field `nmae`, expected `name` or `stable`"
+            )],
+            diagnostics
+        );
+        assert_eq!("dynser", version.name);
+    }
+
+    #[test]
+    fn limits_test() {
+        use crate::dyndeser::{Limit, Limits, Options};
+
+        let read = |input: &str, limits: Limits| {
+            let options = Options {
+                recover: true,
+                limits,
+                ..Default::default()
+            };
+            let mut value = crate::dynamic::DynamicObject::default();
+            crate::dyndeser::read_json_with(input, &mut value, &options).map(|_| value)
+        };
+
+        let input = r#"{"a": {"b": [1, 2, {"c": "long string"}]}, "d": null}"#;
+        read(input, Limits::default()).unwrap();
+
+        let cases = vec![
+            (
+                Limits {
+                    max_depth: Some(3),
+                    ..Default::default()
+                },
+                Limit::Depth,
+                "/a/b/2/c",
+            ),
+            (
+                Limits {
+                    max_nodes: Some(5),
+                    ..Default::default()
+                },
+                Limit::Nodes,
+                "/a/b/2/c",
+            ),
+            (
+                Limits {
+                    max_string_length: Some(5),
+                    ..Default::default()
+                },
+                Limit::StringLength,
+                "/a/b/2/c",
+            ),
+            (
+                Limits {
+                    max_list_length: Some(2),
+                    ..Default::default()
+                },
+                Limit::ListLength,
+                "/a/b",
+            ),
+            (
+                Limits {
+                    max_map_size: Some(1),
+                    ..Default::default()
+                },
+                Limit::MapSize,
+                "",
+            ),
+        ];
+        for (limits, limit, pointer) in cases {
+            let err = read(input, limits).unwrap_err();
+            assert_eq!(Some(limit), err.limit());
+            assert_eq!(pointer, err.pointer());
+        }
+        let err = read(
+            input,
+            Limits {
+                max_depth: Some(3),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            "/a/b/2/c: nesting depth limit of 3 exceeded at line 1 column 25",
+            err.to_string()
+        );
+
+        // Fields buffered until the type is known are limited, too
+        let mut types = crate::registry::TypeRegistry::default();
+        types.register::<Version>("version");
+        let options = Options {
+            limits: Limits {
+                max_string_length: Some(12),
+                ..Default::default()
+            },
+            types: std::sync::Arc::new(types),
+            ..Default::default()
+        };
+        let input = r#"{"v": {"name": "a much longer string", "type": "version"}}"#;
+        let mut objects =
+            std::collections::HashMap::<String, crate::registry::Discriminated>::new();
+        let err = crate::dyndeser::read_json_with(input, &mut objects, &options).unwrap_err();
+        assert_eq!(Some(Limit::StringLength), err.limit());
+        assert_eq!("/v/name", err.pointer());
+
+        let err =
+            crate::dyndeser::read_json(r#"{"name": 1}"#, &mut Version::default()).unwrap_err();
+        assert_eq!(None, err.limit());
+    }
+
+    #[test]
+    fn unknown_fields_test() {
+        use crate::dyndeser::{Options, UnknownFields};
+
+        let input = r#"{"name": "dynser", "stabel": true}"#;
+
+        let mut version = Version::default();
+        let diagnostics =
+            crate::dyndeser::read_json_with(input, &mut version, &Options::default()).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!("dynser", version.name);
+
+        let options = Options {
+            unknown_fields: UnknownFields::Deny,
+            ..Default::default()
+        };
+        let mut version = Version::default();
+        let err = crate::dyndeser::read_json_with(input, &mut version, &options).unwrap_err();
+        assert_eq!("/stabel", err.pointer());
+        assert_eq!(
+            "/stabel: unknown field `stabel`, expected `name` or `stable` at line 1 column 29",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn numbers_test() {
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Numbers {
+            signed: i64,
+            narrow: i32,
+            unsigned: u64,
+            #[primitive]
+            max_length: Option<u32>,
+            multiple_of: f64,
+            values: Vec<f64>,
+        }
+
+        let input = r#"{"signed": -3, "narrow": 7, "unsigned": 18446744073709551615,
+            "max_length": 255, "multiple_of": 2, "values": [0.5, 1, -1]}"#;
+        let mut numbers = Numbers::default();
+        crate::dyndeser::read_json(input, &mut numbers).unwrap();
+        let expected = Numbers {
+            signed: -3,
+            narrow: 7,
+            unsigned: u64::max_value(),
+            max_length: Some(255),
+            multiple_of: 2.0,
+            values: vec![0.5, 1.0, -1.0],
+        };
+        assert_eq!(expected, numbers);
+
+        let mut reparsed = Numbers::default();
+        let output = crate::dynser::write_json(&numbers).unwrap();
+        crate::dyndeser::read_json(&output, &mut reparsed).unwrap();
+        assert_eq!(expected, reparsed);
+
+        let check_error = |input: &str, message: &str| {
+            let mut numbers = Numbers::default();
+            let err = crate::dyndeser::read_json(input, &mut numbers).unwrap_err();
+            assert_eq!(message, err.to_string());
+        };
+        check_error(
+            r#"{"narrow": 4294967296}"#,
+            "/narrow: invalid value: integer `4294967296`, expected i32 at line 1 column 21",
+        );
+        check_error(
+            r#"{"max_length": -1}"#,
+            "/max_length: invalid value: integer `-1`, expected u32 at line 1 column 17",
+        );
+        check_error(
+            r#"{"signed": 1.5}"#,
+            "/signed: invalid type: floating point `1.5`, expected I64 at line 1 column 14",
+        );
+        check_error(
+            r#"{"values": ["1"]}"#,
+            "/values/0: invalid type: string, expected F64 at line 1 column 15",
+        );
+    }
+
+    #[test]
+    fn null_test() {
+        let input = r#"{
+  "openapi": "3.0.0",
+  "info": {"title": "Test", "description": null, "version": "1", "x-logo": null},
+  "paths": {
+    "/items": {
+      "summary": null,
+      "get": {
+        "parameters": [{"name": "id", "in": "path", "example": null}],
+        "responses": {}
+      }
+    }
+  },
+  "components": null
+}"#;
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(input, &mut openapi).unwrap();
+        assert_eq!(None, openapi.info.description);
+        #[cfg(not(feature = "no-flatten"))]
+        assert_eq!(
+            Some(&crate::dynamic::DynamicObject::Null),
+            openapi.info.extensions.get("x-logo")
+        );
+        assert_eq!(None, openapi.paths["/items"].summary);
+        assert_eq!(None, openapi.components);
+
+        let serde_openapi: crate::openapi::OpenApi = serde_json::from_str(input).unwrap();
+        assert_eq!(serde_openapi, openapi);
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        let err =
+            crate::dyndeser::read_json(r#"{"info": {"title": null}}"#, &mut openapi).unwrap_err();
+        assert_eq!(
+            "/info/title: Value cannot be null at line 1 column 24",
+            err.to_string()
+        );
+
+        let mut version = Version::default();
+        let err = crate::dyndeser::read_json(r#"{"stable": null}"#, &mut version).unwrap_err();
+        assert_eq!("/stable", err.pointer());
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        let input = r#"{"paths": {"/items": {"get": {"tags": ["a", null]}}}}"#;
+        let err = crate::dyndeser::read_json(input, &mut openapi).unwrap_err();
+        assert_eq!("/paths/~1items/get/tags/1", err.pointer());
+
+        // Dynamic values are nullable themselves
+        #[derive(Default, Debug, dynser_derive::Object)]
+        struct Example {
+            #[any]
+            value: crate::dynamic::DynamicObject,
+        }
+
+        let mut example = Example {
+            value: crate::dynamic::DynamicObject::List(Vec::new()),
+        };
+        crate::dyndeser::read_json(r#"{"value": null}"#, &mut example).unwrap();
+        assert!(example.value.is_null());
+    }
+
+    #[test]
+    fn required_test() {
+        let input = r#"{
+  "openapi": "3.0.0",
+  "info": {"version": "1"},
+  "paths": {}
+}"#;
+        let mut openapi = crate::openapi::OpenApi::default();
+        let err = crate::dyndeser::read_json(input, &mut openapi).unwrap_err();
+        assert_eq!(
+            "/info: missing field `title` at line 3 column 26",
+            err.to_string()
+        );
+        assert!(serde_json::from_str::<crate::openapi::OpenApi>(input).is_err());
+
+        // Empty value is still a value
+        let input = r#"{"openapi": "", "info": {"title": "", "version": ""}, "paths": {}}"#;
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(input, &mut openapi).unwrap();
+
+        // `$ref` is only allowed in place of references
+        let input = r##"{"name": "items", "externalDocs": {"$ref": "#/docs"}}"##;
+        let mut tag = crate::openapi::Tag::default();
+        let err = crate::dyndeser::read_json(input, &mut tag).unwrap_err();
+        assert_eq!("/externalDocs", err.pointer());
+
+        // References are only checked once we know it is not a reference
+        #[cfg(not(feature = "no-flatten"))]
+        {
+            let input =
+                r##"{"parameters": [{"$ref": "#/param"}, {"in": "query"}], "responses": {}}"##;
+            let mut operation = crate::openapi::Operation::default();
+            let err = crate::dyndeser::read_json(input, &mut operation).unwrap_err();
+            assert_eq!("/parameters/1", err.pointer());
+            assert_eq!(
+                "/parameters/1: missing field `name` at line 1 column 52",
+                err.to_string()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "no-flatten")]
+    fn no_flatten_reference_test() {
+        // References are read as the values themselves, so required fields could be missing
+        let input = r##"{"parameters": [{"$ref": "#/param"}], "responses": {}}"##;
+        let mut operation = crate::openapi::Operation::default();
+        crate::dyndeser::read_json(input, &mut operation).unwrap();
+        assert_eq!(
+            vec![crate::openapi::Parameter::default()],
+            operation.parameters
+        );
+    }
+
+    #[test]
+    fn rename_test() {
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        #[dynser(rename_all = "kebab-case")]
+        struct Release {
+            release_name: String,
+            #[dynser(rename = "$ref")]
+            reference: Option<String>,
+        }
+
+        let input = r##"{"release-name": "1.0", "$ref": "#/releases/1"}"##;
+        let mut release = Release::default();
+        crate::dyndeser::read_json(input, &mut release).unwrap();
+        assert_eq!(
+            Release {
+                release_name: "1.0".to_owned(),
+                reference: Some("#/releases/1".to_owned()),
+            },
+            release
+        );
+        assert_eq!(
+            r##"{"release-name":"1.0","$ref":"#/releases/1"}"##,
+            crate::dynser::write_json(&release).unwrap()
+        );
+
+        // Original field names are not accepted anymore
+        let input = r#"{"release_name": "1.0"}"#;
+        let options = crate::dyndeser::Options {
+            unknown_fields: crate::dyndeser::UnknownFields::Deny,
+            ..Default::default()
+        };
+        let err = crate::dyndeser::read_json_with(input, &mut release, &options).unwrap_err();
+        assert_eq!("/release_name", err.pointer());
+    }
+
+    #[test]
+    fn alias_test() {
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Logo {
+            #[required]
+            #[dynser(alias = "href", alias = "link")]
+            url: String,
+            #[dynser(rename = "altText", alias = "alt")]
+            alt_text: Option<String>,
+        }
+
+        for input in &[
+            r#"{"url": "logo.png", "altText": "Logo"}"#,
+            r#"{"href": "logo.png", "alt": "Logo"}"#,
+            r#"{"link": "logo.png", "alt": "Logo"}"#,
+        ] {
+            let mut logo = Logo::default();
+            crate::dyndeser::read_json(input, &mut logo).unwrap();
+            assert_eq!(
+                Logo {
+                    url: "logo.png".to_owned(),
+                    alt_text: Some("Logo".to_owned()),
+                },
+                logo
+            );
+        }
+
+        // Aliases are only accepted on input
+        let logo = Logo {
+            url: "logo.png".to_owned(),
+            alt_text: None,
+        };
+        assert_eq!(
+            r#"{"url":"logo.png"}"#,
+            crate::dynser::write_json(&logo).unwrap()
+        );
+
+        let mut logo = Logo::default();
+        let err = crate::dyndeser::read_json(r#"{"alt": "Logo"}"#, &mut logo).unwrap_err();
+        assert_eq!("missing field `url` at line 1 column 15", err.to_string());
+    }
+
+    #[test]
+    fn enum_test() {
+        use crate::openapi::{Parameter, ParameterLocation, ParameterStyle};
+
+        // Unit enums are strings
+        let input = r#"{"name": "id", "in": "path", "style": "deepObject"}"#;
+        let mut parameter = Parameter::default();
+        crate::dyndeser::read_json(input, &mut parameter).unwrap();
+        assert_eq!(ParameterLocation::Path, parameter.r#in);
+        assert_eq!(Some(ParameterStyle::DeepObject), parameter.style);
+        assert_eq!(
+            r#"{"name":"id","in":"path","required":false,"deprecated":false,"allowEmptyValue":false,"style":"deepObject","explode":false,"allowReserved":false,"examples":{},"content":{}}"#,
+            crate::dynser::write_json(&parameter).unwrap()
+        );
+
+        let input = r#"{"name": "id", "in": "body"}"#;
+        let err = crate::dyndeser::read_json(input, &mut parameter).unwrap_err();
+        assert_eq!(
+            "/in: unknown variant `body`, expected one of `query`, `header`, `path`, `cookie` at line 1 column 27",
+            err.to_string()
+        );
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Circle {
+            radius: f64,
+        }
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Polygon {
+            points: Vec<f64>,
+        }
+
+        #[derive(Debug, PartialEq, dynser_derive::Object)]
+        #[dynser(rename_all = "snake_case")]
+        enum External {
+            Circle(Circle),
+            Polygon(Box<Polygon>),
+            Label(String),
+        }
+
+        impl Default for External {
+            fn default() -> Self {
+                External::Label(String::new())
+            }
+        }
+
+        #[derive(Debug, PartialEq, dynser_derive::Object)]
+        #[dynser(tag = "kind", rename_all = "snake_case")]
+        enum Internal {
+            Circle(Circle),
+            Polygon(Box<Polygon>),
+            Empty,
+        }
+
+        impl Default for Internal {
+            fn default() -> Self {
+                Internal::Empty
+            }
+        }
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Shapes {
+            external: Vec<External>,
+            internal: Vec<Internal>,
+        }
+
+        let input = r#"{
+  "external": [{"circle": {"radius": 1.5}}, {"polygon": {"points": [1, 2]}}, {"label": "A"}],
+  "internal": [{"kind": "circle", "radius": 1.5}, {"kind": "polygon", "points": [1, 2]}, {"kind": "empty"}]
+}"#;
+        let mut shapes = Shapes::default();
+        crate::dyndeser::read_json(input, &mut shapes).unwrap();
+        assert_eq!(
+            Shapes {
+                external: vec![
+                    External::Circle(Circle { radius: 1.5 }),
+                    External::Polygon(Box::new(Polygon {
+                        points: vec![1.0, 2.0]
+                    })),
+                    External::Label("A".to_owned()),
+                ],
+                internal: vec![
+                    Internal::Circle(Circle { radius: 1.5 }),
+                    Internal::Polygon(Box::new(Polygon {
+                        points: vec![1.0, 2.0]
+                    })),
+                    Internal::Empty,
+                ],
+            },
+            shapes
+        );
+        assert_eq!(
+            r#"{"external":[{"circle":{"radius":1.5}},{"polygon":{"points":[1.0,2.0]}},{"label":"A"}],"internal":[{"kind":"circle","radius":1.5},{"kind":"polygon","points":[1.0,2.0]},{"kind":"empty"}]}"#,
+            crate::dynser::write_json(&shapes).unwrap()
+        );
+
+        // Fields before the tag are buffered until the tag selects the variant
+        let input = r#"{"internal": [{"points": [3], "kind": "polygon"}, {"radius": 2, "kind": "circle"}]}"#;
+        let mut shapes = Shapes::default();
+        crate::dyndeser::read_json(input, &mut shapes).unwrap();
+        assert_eq!(
+            vec![
+                Internal::Polygon(Box::new(Polygon { points: vec![3.0] })),
+                Internal::Circle(Circle { radius: 2.0 }),
+            ],
+            shapes.internal
+        );
+
+        // Without the tag, there is no variant to put the fields into
+        let mut shapes = Shapes::default();
+        let err = crate::dyndeser::read_json(r#"{"internal": [{"radius": 2}]}"#, &mut shapes)
+            .unwrap_err();
+        assert_eq!(
+            "/internal/0: missing field `kind` at line 1 column 27",
+            err.to_string()
+        );
+
+        let input =
+            r#"{"external": [{"square": {}}], "internal": [{"kind": "square"}, {"radius": 2}]}"#;
+        let options = crate::dyndeser::Options {
+            recover: true,
+            ..Default::default()
+        };
+        let mut shapes = Shapes::default();
+        let diagnostics = crate::dyndeser::read_json_with(input, &mut shapes, &options).unwrap();
+        let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "/external/0/square: unknown variant `square`, expected one of `circle`, `polygon`, `label`",
+                "/internal/0/kind: unknown variant `square`, expected one of `circle`, `polygon`, `empty`",
+                "/internal/1: missing field `kind`",
+            ],
+            messages
+        );
+        // Unknown tag is skipped, but the item without the tag is skipped as a whole
+        assert_eq!(vec![Internal::Empty], shapes.internal);
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-flatten"))]
+    fn untagged_test() {
+        use crate::openapi::{Parameter, Reference, ReferenceValue};
+        use std::collections::HashMap;
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Schema {
+            title: String,
+        }
+
+        #[derive(Debug, PartialEq, dynser_derive::Object)]
+        #[dynser(untagged)]
+        enum AdditionalProperties {
+            Bool(bool),
+            Names(Vec<String>),
+            Schema(Box<Schema>),
+        }
+
+        impl Default for AdditionalProperties {
+            fn default() -> Self {
+                AdditionalProperties::Bool(true)
+            }
+        }
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Unions {
+            parameters: Vec<Reference<Parameter>>,
+            schemas: Vec<Reference<HashMap<String, String>>>,
+            properties: Vec<AdditionalProperties>,
+        }
+
+        // Variant is chosen by the first distinguishing field, fields known to multiple variants
+        // are buffered until variants are told apart.
+        let input = r##"{
+  "parameters": [{"$ref": "#/components/parameters/id"}, {"in": "path", "name": "id"}],
+  "schemas": [{"$ref": "#/a"}, {"$ref": "#/b", "description": "B"}],
+  "properties": [false, ["a", "b"], {"title": "T"}]
+}"##;
+        let mut unions = Unions::default();
+        crate::dyndeser::read_json(input, &mut unions).unwrap();
+        assert_eq!(
+            Reference::Reference(ReferenceValue {
+                reference: "#/components/parameters/id".to_owned(),
+            }),
+            unions.parameters[0]
+        );
+        match unions.parameters[1] {
+            Reference::Other(ref parameter) => assert_eq!("id", parameter.name),
+            ref other => panic!("expected parameter, got {:?}", other),
+        }
+        let mut schema = HashMap::new();
+        schema.insert("$ref".to_owned(), "#/b".to_owned());
+        schema.insert("description".to_owned(), "B".to_owned());
+        assert_eq!(
+            vec![
+                Reference::Reference(ReferenceValue {
+                    reference: "#/a".to_owned(),
+                }),
+                Reference::Other(schema),
+            ],
+            unions.schemas
+        );
+        assert_eq!(
+            vec![
+                AdditionalProperties::Bool(false),
+                AdditionalProperties::Names(vec!["a".to_owned(), "b".to_owned()]),
+                AdditionalProperties::Schema(Box::new(Schema {
+                    title: "T".to_owned()
+                })),
+            ],
+            unions.properties
+        );
+
+        unions.parameters.clear();
+        unions.schemas.truncate(1);
+        assert_eq!(
+            r##"{"parameters":[],"schemas":[{"$ref":"#/a"}],"properties":[false,["a","b"],{"title":"T"}]}"##,
+            crate::dynser::write_json(&unions).unwrap()
+        );
+
+        let input = r#"{"properties": [1]}"#;
+        let err = crate::dyndeser::read_json(input, &mut unions).unwrap_err();
+        assert_eq!("/properties/0", err.pointer());
+    }
+
+    #[test]
+    #[cfg(not(feature = "no-flatten"))]
+    fn schema_test() {
+        use crate::dynamic::DynamicObject;
+        use crate::openapi::{AdditionalProperties, Reference, Schema, SchemaType};
+
+        let input = r##"{
+  "type": "object",
+  "required": ["id"],
+  "discriminator": {"propertyName": "kind", "mapping": {"dog": "#/components/schemas/Dog"}},
+  "properties": {
+    "id": {"type": "integer", "format": "int64", "minimum": 1, "exclusiveMinimum": true},
+    "name": {"type": "string", "maxLength": 64, "pattern": "^[a-z]+$", "nullable": true},
+    "tags": {"type": "array", "items": {"$ref": "#/components/schemas/Tag"}, "uniqueItems": true},
+    "kind": {"type": "string", "enum": ["cat", "dog"], "default": "cat"},
+    "owner": {"allOf": [{"$ref": "#/components/schemas/Owner"}], "readOnly": true}
+  },
+  "additionalProperties": {"type": "string", "xml": {"name": "extra", "attribute": true}},
+  "not": {"type": "boolean"},
+  "x-internal": true
+}"##;
+        let mut schema = Schema::default();
+        crate::dyndeser::read_json(input, &mut schema).unwrap();
+        let serde_schema: Schema = serde_json::from_str(input).unwrap();
+        assert_eq!(serde_schema, schema);
+
+        assert_eq!(Some(SchemaType::Object), schema.r#type);
+        match schema.properties["kind"] {
+            Reference::Other(ref kind) => assert_eq!(
+                vec![
+                    DynamicObject::from(serde_json::json!("cat")),
+                    DynamicObject::from(serde_json::json!("dog")),
+                ],
+                kind.r#enum
+            ),
+            ref other => panic!("expected schema, got {:?}", other),
+        }
+        match schema.additional_properties {
+            Some(AdditionalProperties::Schema(ref schema)) => match **schema {
+                Reference::Other(ref schema) => {
+                    assert_eq!(Some(SchemaType::String), schema.r#type);
+                    assert!(schema.xml.as_ref().unwrap().attribute);
+                }
+                ref other => panic!("expected schema, got {:?}", other),
+            },
+            ref other => panic!("expected schema, got {:?}", other),
+        }
+
+        let input = r#"{"type": "object", "additionalProperties": false}"#;
+        crate::dyndeser::read_json(input, &mut schema).unwrap();
+        assert_eq!(
+            Some(AdditionalProperties::Bool(false)),
+            schema.additional_properties
+        );
+
+        let output = crate::dynser::write_json(&schema).unwrap();
+        let mut reparsed = Schema::default();
+        crate::dyndeser::read_json(&output, &mut reparsed).unwrap();
+        assert_eq!(schema, reparsed);
+    }
+
+    #[test]
+    fn value_test() {
+        use serde_json::Value;
+
+        // Values take the shape of the incoming data
+        let input = r#"{
+  "name": "dynser",
+  "stable": false,
+  "downloads": 18446744073709551615,
+  "rating": -4.5,
+  "delta": -3,
+  "tags": ["json", null, {"nested": [[], {}]}],
+  "homepage": null
+}"#;
+        let mut value = Value::Null;
+        crate::dyndeser::read_json(input, &mut value).unwrap();
+        let serde_value: Value = serde_json::from_str(input).unwrap();
+        assert_eq!(serde_value, value);
+
+        let output = crate::dynser::write_json(&value).unwrap();
+        assert_eq!(serde_json::to_string(&serde_value).unwrap(), output);
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Config {
+            settings: Value,
+            defaults: Value,
+        }
+
+        let input = r#"{"settings": {"depth": 1}, "defaults": null}"#;
+        let mut config = Config {
+            defaults: Value::Bool(true),
+            ..Default::default()
+        };
+        crate::dyndeser::read_json(input, &mut config).unwrap();
+        assert_eq!(
+            Config {
+                settings: serde_json::json!({"depth": 1}),
+                defaults: Value::Null,
+            },
+            config
+        );
+    }
+
+    #[test]
+    fn dynamic_test() {
+        use crate::dynamic::DynamicObject;
+        use crate::reflection::PrimitiveValue;
+
+        let input =
+            r#"{"size": [1, -2, 3.5], "label": {"text": "A", "hidden": false}, "next": null}"#;
+        let mut dynamic = DynamicObject::default();
+        crate::dyndeser::read_json(input, &mut dynamic).unwrap();
+        let serde_value: serde_json::Value = serde_json::from_str(input).unwrap();
+        assert_eq!(DynamicObject::from(serde_value.clone()), dynamic);
+        assert_eq!(serde_value, serde_json::Value::from(dynamic.clone()));
+
+        let fields = dynamic.as_object().unwrap();
+        assert_eq!(
+            Some(&PrimitiveValue::I64(-2)),
+            fields["size"].as_list().unwrap()[1].as_primitive()
+        );
+        assert!(fields["next"].is_null());
+        assert_eq!(
+            r#"{"label":{"hidden":false,"text":"A"},"next":null,"size":[1,-2,3.5]}"#,
+            crate::dynser::write_json(&dynamic).unwrap()
+        );
+    }
+
+    #[test]
+    fn discriminator_test() {
+        use crate::registry::{Discriminated, TypeRegistry};
+        use std::sync::Arc;
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Circle {
+            radius: f64,
+        }
+
+        #[derive(Default, Debug, PartialEq, dynser_derive::Object)]
+        struct Label {
+            kind: String,
+            text: String,
+        }
+
+        #[derive(Default, Debug, dynser_derive::Object)]
+        struct Drawing {
+            shapes: Vec<Discriminated>,
+            background: Option<Discriminated>,
+        }
+
+        let mut types = TypeRegistry::new("kind");
+        types
+            .register::<Circle>("circle")
+            .register::<Label>("label");
+        let options = crate::dyndeser::Options {
+            types: Arc::new(types),
+            ..Default::default()
+        };
+
+        // Fields before the discriminator are replayed into the object once it is created
+        let input = r#"{
+  "shapes": [{"kind": "circle", "radius": 1.5}, {"text": "A", "kind": "label"}],
+  "background": {"radius": 10, "kind": "circle"}
+}"#;
+        let mut drawing = Drawing::default();
+        crate::dyndeser::read_json_with(input, &mut drawing, &options).unwrap();
+        assert_eq!(
+            Some(&Circle { radius: 1.5 }),
+            drawing.shapes[0].downcast_ref::<Circle>()
+        );
+        assert_eq!(
+            Some(&Label {
+                kind: "label".to_owned(),
+                text: "A".to_owned(),
+            }),
+            drawing.shapes[1].downcast_ref::<Label>()
+        );
+        assert_eq!(
+            Some("circle"),
+            drawing
+                .background
+                .as_ref()
+                .and_then(Discriminated::type_name)
+        );
+        assert_eq!(
+            r#"{"shapes":[{"kind":"circle","radius":1.5},{"kind":"label","text":"A"}],"background":{"kind":"circle","radius":10.0}}"#,
+            crate::dynser::write_json(&drawing).unwrap()
+        );
+
+        let input = r#"{"shapes": [{"kind": "square", "side": 1}, {"radius": 1}, {"kind": 1}]}"#;
+        let options = crate::dyndeser::Options {
+            recover: true,
+            ..options
+        };
+        let mut drawing = Drawing::default();
+        let diagnostics = crate::dyndeser::read_json_with(input, &mut drawing, &options).unwrap();
+        let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "/shapes/0/kind: unknown type `square`, expected one of `circle`, `label`",
+                "/shapes/1: missing field `kind`",
+                "/shapes/2/kind: invalid type: integer `1`, expected a string",
+            ],
+            messages
+        );
+        assert_eq!(2, drawing.shapes.len());
+        assert_eq!(None, drawing.shapes[0].type_name());
+    }
+
+    #[test]
+    fn walk_test() {
+        use crate::reflection::{FieldRefReflection, ObjectRef, PrimitiveValueRef};
+
+        fn collect_operation_ids(object: &dyn ObjectRef, ids: &mut Vec<String>) {
+            for (name, field) in object.fields() {
+                match field {
+                    FieldRefReflection::Primitive(primitive) => {
+                        if let ("operationId", PrimitiveValueRef::String(id)) =
+                            (name, primitive.value())
+                        {
+                            ids.push(id.to_owned());
+                        }
+                    }
+                    FieldRefReflection::Object(object) => collect_operation_ids(object, ids),
+                    FieldRefReflection::List(list) => {
+                        for item in list.iter() {
+                            if let FieldRefReflection::Object(object) = item {
+                                collect_operation_ids(object, ids);
+                            }
+                        }
+                    }
+                    FieldRefReflection::Any(_) | FieldRefReflection::Null => {}
+                }
+            }
+        }
+
+        let mut openapi = crate::openapi::OpenApi::default();
+        crate::dyndeser::read_json(TEST_CASE, &mut openapi).unwrap();
+
+        let mut ids = Vec::new();
+        collect_operation_ids(&openapi, &mut ids);
+        ids.sort();
+
+        let mut expected: Vec<String> = openapi
+            .paths
+            .values()
+            .flat_map(|path| vec![&path.get, &path.post, &path.put, &path.delete])
+            .filter_map(|op| op.as_ref().and_then(|op| op.operation_id.clone()))
+            .collect();
+        expected.sort();
+        assert_eq!(10, ids.len());
+        assert_eq!(expected, ids);
+    }
+
     #[bench]
     fn bench_serde(bencher: &mut Bencher) {
         bencher.iter(|| {
@@ -1,83 +1,81 @@
 //! https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.0.md
-#![allow(non_snake_case)]
+use crate::dynamic::DynamicObject;
 use dynser_derive::Object;
 use serde_derive::Deserialize;
-use serde_json::Value;
 use std::collections::HashMap;
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct OpenApi {
-    #[primitive]
+    #[required]
     pub openapi: String,
+    #[required]
     pub info: Info,
     #[serde(default)]
     pub servers: Vec<Server>,
+    #[required]
     pub paths: Paths,
     pub components: Option<Components>,
     #[serde(default)]
     pub security: Vec<SecurityRequirement>,
     #[serde(default)]
     pub tags: Vec<Tag>,
-    pub externalDocs: Option<ExternalDocumentation>,
+    pub external_docs: Option<ExternalDocumentation>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Info {
-    #[primitive]
+    #[required]
     pub title: String,
-    #[primitive]
     pub description: Option<String>,
-    #[primitive]
-    pub termsOfService: Option<String>,
+    pub terms_of_service: Option<String>,
     pub contact: Option<Contact>,
     pub license: Option<License>,
-    #[primitive]
+    #[required]
     pub version: String,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct Contact {
-    #[primitive]
     pub name: Option<String>,
-    #[primitive]
     pub url: Option<String>,
-    #[primitive]
     pub email: Option<String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct License {
-    #[primitive]
+    #[required]
     pub name: String,
-    #[primitive]
     pub url: Option<String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct Server {
-    #[primitive]
+    #[required]
     pub url: String,
-    #[primitive]
     pub description: Option<String>,
     #[serde(default)]
     pub variables: HashMap<String, ServerVariable>,
@@ -85,26 +83,26 @@ pub struct Server {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct ServerVariable {
     #[serde(default)]
-    #[primitive]
     pub r#enum: Vec<String>,
-    #[primitive]
+    #[required]
     pub r#default: String,
-    #[primitive]
     pub description: Option<String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Components {
     #[serde(default)]
     pub schemas: HashMap<String, Reference<Schema>>,
@@ -115,11 +113,11 @@ pub struct Components {
     #[serde(default)]
     pub examples: HashMap<String, Reference<Example>>,
     #[serde(default)]
-    pub requestBodies: HashMap<String, Reference<RequestBody>>,
+    pub request_bodies: HashMap<String, Reference<RequestBody>>,
     #[serde(default)]
     pub headers: HashMap<String, Reference<Header>>,
     #[serde(default)]
-    pub securitySchemes: HashMap<String, Reference<SecurityScheme>>,
+    pub security_schemes: HashMap<String, Reference<SecurityScheme>>,
     #[serde(default)]
     pub links: HashMap<String, Reference<Link>>,
     #[serde(default)]
@@ -128,20 +126,17 @@ pub struct Components {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 pub type Paths = HashMap<String, Path>;
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct Path {
-    // FIXME: should support "renaming"
-    #[primitive]
+    #[dynser(rename = "$ref")]
     #[serde(rename = "$ref")]
-    pub r#ref: Option<String>,
-    #[primitive]
+    pub reference: Option<String>,
     pub summary: Option<String>,
-    #[primitive]
     pub description: Option<String>,
     pub get: Option<Operation>,
     pub put: Option<Operation>,
@@ -159,29 +154,27 @@ pub struct Path {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Operation {
     #[serde(default)]
-    #[primitive]
     pub tags: Vec<String>,
-    #[primitive]
     pub summary: Option<String>,
-    #[primitive]
     pub description: Option<String>,
-    pub externalDocs: Option<ExternalDocumentation>,
-    #[primitive]
-    pub operationId: Option<String>,
+    pub external_docs: Option<ExternalDocumentation>,
+    pub operation_id: Option<String>,
     #[serde(default)]
     pub parameters: Vec<Reference<Parameter>>,
-    pub requestBody: Option<Reference<RequestBody>>,
+    pub request_body: Option<Reference<RequestBody>>,
+    #[required]
     pub responses: Responses,
     #[serde(default)]
     pub callbacks: HashMap<String, Reference<Callback>>,
     #[serde(default)]
-    #[primitive]
     pub deprecated: bool,
     #[serde(default)]
     pub security: Vec<SecurityRequirement>,
@@ -191,51 +184,83 @@ pub struct Operation {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct ExternalDocumentation {
-    #[primitive]
     pub description: Option<String>,
-    #[primitive]
+    #[required]
     pub url: String,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterLocation {
+    Query,
+    Header,
+    Path,
+    Cookie,
+}
+
+impl Default for ParameterLocation {
+    fn default() -> Self {
+        ParameterLocation::Query
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum ParameterStyle {
+    Matrix,
+    Label,
+    Form,
+    Simple,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+}
+
+impl Default for ParameterStyle {
+    fn default() -> Self {
+        ParameterStyle::Form
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Parameter {
-    #[primitive]
+    // References are read as the values themselves without flattening, so these could be missing
+    #[cfg_attr(not(feature = "no-flatten"), required)]
     pub name: String,
+    #[cfg_attr(not(feature = "no-flatten"), required)]
     #[primitive]
-    pub r#in: String,
-    #[primitive]
+    pub r#in: ParameterLocation,
     pub description: Option<String>,
     #[serde(default)]
-    #[primitive]
     pub required: bool,
     #[serde(default)]
-    #[primitive]
     pub deprecated: bool,
     #[serde(default)]
-    #[primitive]
-    pub allowEmptyValue: bool,
+    pub allow_empty_value: bool,
 
     #[primitive]
-    pub style: Option<String>,
+    pub style: Option<ParameterStyle>,
     #[serde(default)]
-    #[primitive]
     pub explode: bool,
     #[serde(default)]
-    #[primitive]
-    pub allowReserved: bool,
+    pub allow_reserved: bool,
     pub schema: Option<Reference<Schema>>,
     #[any]
-    pub example: Option<Value>,
+    pub example: Option<DynamicObject>,
     #[serde(default)]
     pub examples: HashMap<String, Reference<Example>>,
 
@@ -245,30 +270,28 @@ pub struct Parameter {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct RequestBody {
-    #[primitive]
     pub description: Option<String>,
     #[serde(default)]
     pub content: HashMap<String, MediaType>,
     #[serde(default)]
-    #[primitive]
     pub required: bool,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct MediaType {
     pub schema: Option<Reference<Schema>>,
     #[any]
-    pub example: Option<Value>,
+    pub example: Option<DynamicObject>,
     #[serde(default)]
     pub examples: HashMap<String, Reference<Example>>,
     #[serde(default)]
@@ -277,35 +300,32 @@ pub struct MediaType {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Encoding {
-    #[primitive]
-    pub contentType: Option<String>,
+    pub content_type: Option<String>,
     #[serde(default)]
     pub headers: HashMap<String, Reference<Header>>,
-    #[primitive]
     pub style: Option<String>,
     #[serde(default)]
-    #[primitive]
     pub explode: bool,
     #[serde(default)]
-    #[primitive]
-    pub allowReserved: bool,
+    pub allow_reserved: bool,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 pub type Responses = HashMap<String, Reference<Response>>;
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
 pub struct Response {
-    #[primitive]
     pub description: Option<String>,
     #[serde(default)]
     pub headers: HashMap<String, Reference<Header>>,
@@ -317,124 +337,261 @@ pub struct Response {
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 pub type Callback = HashMap<String, Path>;
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Example {
-    #[primitive]
     pub summary: Option<String>,
-    #[primitive]
     pub description: Option<String>,
     #[any]
-    pub value: Option<Value>,
-    #[primitive]
-    #[primitive]
-    pub externalValue: Option<String>,
+    pub value: Option<DynamicObject>,
+    pub external_value: Option<String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Link {
-    #[primitive]
-    pub operationRef: Option<String>,
-    #[primitive]
-    pub operationId: Option<String>,
+    pub operation_ref: Option<String>,
+    pub operation_id: Option<String>,
     #[serde(default)]
-    pub parameters: HashMap<String, Value>,
+    pub parameters: HashMap<String, DynamicObject>,
     #[any]
-    pub requestBody: Option<Value>,
-    #[primitive]
+    pub request_body: Option<DynamicObject>,
     pub description: Option<String>,
     pub server: Option<Server>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 pub type Header = Parameter;
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct Tag {
-    #[primitive]
+    #[required]
     pub name: String,
+    pub description: Option<String>,
+    pub external_docs: Option<ExternalDocumentation>,
+
+    #[cfg(not(feature = "no-flatten"))]
+    #[serde(flatten)]
+    #[default]
+    pub extensions: HashMap<String, DynamicObject>,
+}
+
+#[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub title: Option<String>,
+    pub multiple_of: Option<f64>,
+    pub maximum: Option<f64>,
+    #[serde(default)]
+    pub exclusive_maximum: bool,
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub exclusive_minimum: bool,
+    pub max_length: Option<u64>,
+    pub min_length: Option<u64>,
+    pub pattern: Option<String>,
+    pub max_items: Option<u64>,
+    pub min_items: Option<u64>,
+    #[serde(default)]
+    pub unique_items: bool,
+    pub max_properties: Option<u64>,
+    pub min_properties: Option<u64>,
+    #[serde(default)]
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub r#enum: Vec<DynamicObject>,
+
     #[primitive]
+    pub r#type: Option<SchemaType>,
+    #[serde(default)]
+    pub all_of: Vec<Reference<Schema>>,
+    #[serde(default)]
+    pub one_of: Vec<Reference<Schema>>,
+    #[serde(default)]
+    pub any_of: Vec<Reference<Schema>>,
+    pub not: Option<Box<Reference<Schema>>>,
+    pub items: Option<Box<Reference<Schema>>>,
+    #[serde(default)]
+    pub properties: HashMap<String, Reference<Schema>>,
+    pub additional_properties: Option<AdditionalProperties>,
     pub description: Option<String>,
-    pub externalDocs: Option<ExternalDocumentation>,
+    pub format: Option<String>,
+    #[any]
+    pub default: Option<DynamicObject>,
+
+    #[serde(default)]
+    pub nullable: bool,
+    pub discriminator: Option<Discriminator>,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub write_only: bool,
+    pub xml: Option<Xml>,
+    pub external_docs: Option<ExternalDocumentation>,
+    #[any]
+    pub example: Option<DynamicObject>,
+    #[serde(default)]
+    pub deprecated: bool,
+
+    #[cfg(not(feature = "no-flatten"))]
+    #[serde(flatten)]
+    #[default]
+    pub extensions: HashMap<String, DynamicObject>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl Default for SchemaType {
+    fn default() -> Self {
+        SchemaType::Object
+    }
+}
+
+/// Either a flag allowing (or disallowing) any additional properties or a schema for them.
+#[derive(Debug, PartialEq, Deserialize, Object)]
+#[dynser(untagged)]
+#[serde(untagged)]
+pub enum AdditionalProperties {
+    Bool(bool),
+    Schema(Box<Reference<Schema>>),
+}
+
+impl Default for AdditionalProperties {
+    fn default() -> Self {
+        AdditionalProperties::Bool(true)
+    }
+}
+
+#[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub struct Discriminator {
+    #[required]
+    pub property_name: String,
+    #[serde(default)]
+    pub mapping: HashMap<String, String>,
+}
+
+#[derive(Default, Debug, PartialEq, Deserialize, Object)]
+pub struct Xml {
+    pub name: Option<String>,
+    pub namespace: Option<String>,
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub attribute: bool,
+    #[serde(default)]
+    pub wrapped: bool,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
-// FIXME: too big, so let's do JSON for now
-// FIXME: has to do HashMap -- we don't implement `Object` for `Value`!
-pub type Schema = HashMap<String, Value>;
+#[derive(Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
+pub enum SecuritySchemeType {
+    ApiKey,
+    Http,
+    #[dynser(rename = "oauth2")]
+    #[serde(rename = "oauth2")]
+    OAuth2,
+    OpenIdConnect,
+}
+
+impl Default for SecuritySchemeType {
+    fn default() -> Self {
+        SecuritySchemeType::ApiKey
+    }
+}
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct SecurityScheme {
+    // References are read as the values themselves without flattening, so this could be missing
+    #[cfg_attr(not(feature = "no-flatten"), required)]
     #[primitive]
-    pub r#type: String,
+    pub r#type: SecuritySchemeType,
     #[any]
-    pub description: Option<Value>,
+    pub description: Option<DynamicObject>,
     #[serde(default)]
-    #[primitive]
     pub name: String,
     #[serde(default)]
-    #[primitive]
     pub r#in: String,
     #[serde(default)]
-    #[primitive]
     pub scheme: String,
-    #[primitive]
-    pub bearerFormat: Option<String>,
+    pub bearer_format: Option<String>,
     pub flows: Option<OAuthFlows>,
-    #[primitive]
-    pub openIdConnectUrl: Option<String>,
+    pub open_id_connect_url: Option<String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct OAuthFlows {
     pub implicit: Option<OAuthFlow>,
     pub password: Option<OAuthFlow>,
-    pub clientCredentials: Option<OAuthFlow>,
-    pub authorizationCode: Option<OAuthFlow>,
+    pub client_credentials: Option<OAuthFlow>,
+    pub authorization_code: Option<OAuthFlow>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize, Object)]
+#[dynser(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct OAuthFlow {
     #[serde(default)]
-    #[primitive]
-    pub authorizationUrl: String,
-    #[primitive]
-    pub tokenUrl: String,
-    #[primitive]
-    pub refreshUrl: Option<String>,
+    pub authorization_url: String,
+    #[required]
+    pub token_url: String,
+    pub refresh_url: Option<String>,
+    #[required]
     pub scopes: HashMap<String, String>,
 
     #[cfg(not(feature = "no-flatten"))]
     #[serde(flatten)]
     #[default]
-    pub extensions: HashMap<String, Value>,
+    pub extensions: HashMap<String, DynamicObject>,
 }
 
 pub type SecurityRequirement = HashMap<String, Vec<String>>;
@@ -448,73 +605,31 @@ pub use self::reference::{Reference, ReferenceValue};
 
 #[cfg(not(feature = "no-flatten"))]
 mod reference {
-    use crate::reflection::{FieldMutReflection, Object, ReflectionError};
+    use dynser_derive::Object;
     use serde_derive::Deserialize;
 
-    #[derive(Default, Debug, PartialEq, Deserialize)]
+    #[derive(Default, Debug, PartialEq, Deserialize, Object)]
     #[serde(deny_unknown_fields)]
     pub struct ReferenceValue {
+        #[required]
+        #[dynser(rename = "$ref")]
         #[serde(rename = "$ref")]
-        reference: String,
+        pub reference: String,
     }
 
-    #[derive(Debug, PartialEq, Deserialize)]
+    /// Either a reference to the value defined elsewhere or the value itself. References are told
+    /// apart by the `$ref` field.
+    #[derive(Debug, PartialEq, Deserialize, Object)]
+    #[dynser(untagged)]
     #[serde(untagged)]
     pub enum Reference<T> {
         Reference(ReferenceValue),
         Other(T),
     }
 
-    // FIXME: ugly -- we use empty reference as an indication of "no value"
-    // This makes `$ref: ""` to have no effect, etc.
-    impl<T> Default for Reference<T> {
+    impl<T: Default> Default for Reference<T> {
         fn default() -> Self {
-            Reference::Reference(Default::default())
-        }
-    }
-
-    impl<T> Object for Reference<T>
-    where
-        T: Object + Default,
-    {
-        fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
-            match self {
-                Reference::Other(other) => other.create(field_name),
-                Reference::Reference { .. } if field_name == "$ref" => match self {
-                    Reference::Reference(r) => Ok(FieldMutReflection::Primitive(&mut r.reference)),
-                    Reference::Other(_) => unreachable!(),
-                },
-                Reference::Reference { .. } => {
-                    let previous_ref = if let Reference::Reference(r) = self {
-                        std::mem::replace(&mut r.reference, String::new())
-                    } else {
-                        unreachable!()
-                    };
-
-                    // Mutate ourselves into other
-                    *self = Reference::Other(T::default());
-                    match self {
-                        Reference::Reference { .. } => unreachable!(),
-                        Reference::Other(other) => {
-                            // Move previously saved reference to the new object
-                            if !previous_ref.is_empty() {
-                                use crate::reflection::PrimitiveValue;
-                                match other.create("$ref")? {
-                                    FieldMutReflection::Primitive(primitive) => {
-                                        primitive.set(PrimitiveValue::String(previous_ref))?;
-                                    }
-                                    _ => {
-                                        return Err(ReflectionError::InvalidField {
-                                            name: "$ref".to_string(),
-                                        })
-                                    }
-                                }
-                            }
-                            other.create(field_name)
-                        }
-                    }
-                }
-            }
+            Reference::Other(T::default())
         }
     }
 }
@@ -1,5 +1,8 @@
+use crate::dynamic::DynamicObject;
 use failure::Fail;
+use std::any::Any;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 #[derive(Debug, Fail)]
 pub enum ReflectionError {
@@ -13,45 +16,329 @@ pub enum ReflectionError {
     },
     #[fail(display = "Field '{}' does not exist", name)]
     InvalidField { name: String },
+    #[fail(display = "Value cannot be null")]
+    NotNullable,
+    #[fail(display = "Value '{}' is out of range for '{}'", value, expected)]
+    OutOfRange {
+        /// Name of the target type, like `i32`.
+        expected: &'static str,
+        value: String,
+    },
+    #[fail(
+        display = "Unknown variant '{}', expected one of {:?}",
+        value, expected
+    )]
+    UnknownVariant {
+        value: String,
+        expected: &'static [&'static str],
+    },
+    #[fail(display = "Field '{}' is missing", name)]
+    MissingField { name: &'static str },
 }
 
 // Primitive values
 
-#[derive(Debug, Clone, Copy)]
+/// Kind of the primitive value. Integer kinds cover all integer types of the same signedness,
+/// conversion to the narrower types is checked when value is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PrimitiveValueKind {
     String,
     Bool,
+    I64,
+    U64,
+    F64,
+}
+
+impl PrimitiveValueKind {
+    /// Check if primitive of this kind can be set to the value of the given kind. Numbers are
+    /// accepted by the floating point primitives and integers by the integer primitives of any
+    /// signedness (range is checked when value is set).
+    pub fn accepts(self, value: PrimitiveValueKind) -> bool {
+        use PrimitiveValueKind::*;
+        match (self, value) {
+            (String, String) | (Bool, Bool) => true,
+            (I64, I64) | (I64, U64) | (U64, I64) | (U64, U64) => true,
+            (F64, I64) | (F64, U64) | (F64, F64) => true,
+            _ => false,
+        }
+    }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum PrimitiveValue {
     String(String),
     Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
 }
 
 impl PrimitiveValue {
-    fn kind(&self) -> PrimitiveValueKind {
+    pub fn kind(&self) -> PrimitiveValueKind {
         match self {
             PrimitiveValue::String(_) => PrimitiveValueKind::String,
             PrimitiveValue::Bool(_) => PrimitiveValueKind::Bool,
+            PrimitiveValue::I64(_) => PrimitiveValueKind::I64,
+            PrimitiveValue::U64(_) => PrimitiveValueKind::U64,
+            PrimitiveValue::F64(_) => PrimitiveValueKind::F64,
         }
     }
 }
 
+impl PrimitiveRef for PrimitiveValue {
+    fn value(&self) -> PrimitiveValueRef {
+        match self {
+            PrimitiveValue::String(v) => PrimitiveValueRef::String(v),
+            PrimitiveValue::Bool(v) => PrimitiveValueRef::Bool(*v),
+            PrimitiveValue::I64(v) => PrimitiveValueRef::I64(*v),
+            PrimitiveValue::U64(v) => PrimitiveValueRef::U64(*v),
+            PrimitiveValue::F64(v) => PrimitiveValueRef::F64(*v),
+        }
+    }
+}
+
+/// Borrowed view of a primitive value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrimitiveValueRef<'a> {
+    String(&'a str),
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+}
+
+impl<'a> PrimitiveValueRef<'a> {
+    pub fn kind(&self) -> PrimitiveValueKind {
+        match self {
+            PrimitiveValueRef::String(_) => PrimitiveValueKind::String,
+            PrimitiveValueRef::Bool(_) => PrimitiveValueKind::Bool,
+            PrimitiveValueRef::I64(_) => PrimitiveValueKind::I64,
+            PrimitiveValueRef::U64(_) => PrimitiveValueKind::U64,
+            PrimitiveValueRef::F64(_) => PrimitiveValueKind::F64,
+        }
+    }
+}
+
+impl<'a> From<&'a String> for PrimitiveValueRef<'a> {
+    fn from(value: &'a String) -> Self {
+        PrimitiveValueRef::String(value)
+    }
+}
+
+impl<'a> From<&'a bool> for PrimitiveValueRef<'a> {
+    fn from(value: &'a bool) -> Self {
+        PrimitiveValueRef::Bool(*value)
+    }
+}
+
+impl<'a> From<&'a f64> for PrimitiveValueRef<'a> {
+    fn from(value: &'a f64) -> Self {
+        PrimitiveValueRef::F64(*value)
+    }
+}
+
 // Reflection API
 
 pub enum FieldMutReflection<'a> {
     Object(&'a mut dyn Object),
     List(&'a mut dyn List),
     Primitive(&'a mut dyn Primitive),
-    Any(&'a mut serde_json::Value),
+    Any(&'a mut DynamicObject),
+    Polymorphic(&'a mut dyn Polymorphic),
 }
 
 pub trait Object {
     fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError>;
+
+    /// Set field to an explicit `null`. Optional fields are reset to `None`.
+    fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+        let _ = field_name;
+        Err(ReflectionError::NotNullable)
+    }
+
+    /// Undo `create` of the field whose value was skipped (in the recovery mode), so the field is
+    /// left as it was. Optional fields are reset to `None` and map entries are removed.
+    fn discard(&mut self, field_name: &str) {
+        let _ = field_name;
+    }
+
+    /// Set the object itself to an explicit `null`, for objects which could be `null` (like JSON
+    /// values). Used by the containers of such objects to accept `null` values.
+    fn make_null(&mut self) -> Result<(), ReflectionError> {
+        Err(ReflectionError::NotNullable)
+    }
+
+    /// Names of the fields known to this object, for error reporting. Objects accepting any field
+    /// name (like maps) return an empty list.
+    fn field_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Names of the fields which must be present in the input.
+    fn required_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Alternative names accepted for the fields, as `(alias, field name)` pairs.
+    fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    // Untagged unions. Objects which could take one of several shapes select the shape by the
+    // kind of value given in place of the object or, for objects, by the first field which tells
+    // the shapes apart.
+
+    /// Select the variant by the names of the fields seen so far, the last one being the new
+    /// field. Returns `false` if fields do not tell variants apart yet; such fields are buffered by
+    /// the deserializer until the variant is selected.
+    fn select_variant(&mut self, field_names: &[&str]) -> bool {
+        let _ = field_names;
+        true
+    }
+
+    /// Select the variant when fields did not tell variants apart, given all the fields. Fails if
+    /// no variant could be selected without the missing field (like the tag of internally tagged
+    /// enums); the fields are skipped then.
+    fn select_default_variant(&mut self, field_names: &[&str]) -> Result<(), ReflectionError> {
+        let _ = field_names;
+        Ok(())
+    }
+
+    /// Select the variant accepting primitive of the given kind in place of the object.
+    fn select_primitive(&mut self, kind: PrimitiveValueKind) -> Option<&mut dyn Primitive> {
+        let _ = kind;
+        None
+    }
+
+    /// Select the variant accepting list in place of the object.
+    fn select_list(&mut self) -> Option<&mut dyn List> {
+        None
+    }
+}
+
+impl<'a> dyn Object + 'a {
+    /// Check if all given fields are known to the object. Objects which do not know their fields
+    /// (like maps) accept any field.
+    pub fn knows_fields(&self, field_names: &[&str]) -> bool {
+        knows_fields(self.field_names(), self.aliases(), field_names)
+    }
+
+    /// Required fields which are not among the given fields (either by name or by alias).
+    pub fn missing_fields(&self, field_names: &[&str]) -> Vec<&'static str> {
+        missing_fields(self.required_fields(), self.aliases(), field_names)
+    }
+}
+
+/// Fields of the object type, known without the value. Untagged unions use these to select the
+/// variant by the fields seen. Same as the `Object` methods of the same names; types which do not
+/// list their fields (like maps) accept any field.
+pub trait ObjectFields {
+    const FIELD_NAMES: &'static [&'static str] = &[];
+    const REQUIRED_FIELDS: &'static [&'static str] = &[];
+    const ALIASES: &'static [(&'static str, &'static str)] = &[];
+
+    /// Check if all given fields are known to the objects of this type.
+    fn knows_fields(field_names: &[&str]) -> bool {
+        knows_fields(Self::FIELD_NAMES, Self::ALIASES, field_names)
+    }
+
+    /// Required fields which are not among the given fields (either by name or by alias).
+    fn missing_fields(field_names: &[&str]) -> Vec<&'static str> {
+        missing_fields(Self::REQUIRED_FIELDS, Self::ALIASES, field_names)
+    }
+}
+
+fn knows_fields(
+    known: &[&str],
+    aliases: &[(&'static str, &'static str)],
+    field_names: &[&str],
+) -> bool {
+    known.is_empty()
+        || field_names
+            .iter()
+            .all(|name| known.contains(name) || aliases.iter().any(|(alias, _)| alias == name))
+}
+
+fn missing_fields(
+    required: &[&'static str],
+    aliases: &[(&'static str, &'static str)],
+    field_names: &[&str],
+) -> Vec<&'static str> {
+    required
+        .iter()
+        .cloned()
+        .filter(|required| {
+            !field_names.iter().any(|name| {
+                name == required
+                    || aliases
+                        .iter()
+                        .any(|(alias, field)| field == required && name == alias)
+            })
+        })
+        .collect()
+}
+
+/// Object of the type selected at runtime, by the name given in the discriminator field.
+pub trait Polymorphic {
+    /// Set the object of the given type, returning it to set the rest of the fields.
+    fn set_object(
+        &mut self,
+        discriminator: &str,
+        type_name: &str,
+        object: Box<dyn DynObject>,
+    ) -> &mut dyn Object;
+}
+
+/// Object created at runtime, supporting both reflection APIs.
+pub trait DynObject: Object + ObjectRef + Any {
+    fn as_object(&mut self) -> &mut dyn Object;
+    fn as_object_ref(&self) -> &dyn ObjectRef;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T> DynObject for T
+where
+    T: Object + ObjectRef + Any,
+{
+    fn as_object(&mut self) -> &mut dyn Object {
+        self
+    }
+
+    fn as_object_ref(&self) -> &dyn ObjectRef {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl dyn DynObject {
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
 }
 
 pub trait List {
     fn push(&mut self) -> FieldMutReflection;
+
+    /// Push an explicit `null` to the list.
+    fn push_null(&mut self) -> Result<(), ReflectionError> {
+        Err(ReflectionError::NotNullable)
+    }
+
+    /// Undo `push` of the item whose value was skipped (in the recovery mode), so the item is left
+    /// out of the list.
+    fn discard_last(&mut self) {}
 }
 
 pub trait Primitive {
@@ -59,6 +346,63 @@ pub trait Primitive {
     fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError>;
 }
 
+// Read-only reflection API
+
+pub enum FieldRefReflection<'a> {
+    Object(&'a dyn ObjectRef),
+    List(&'a dyn ListRef),
+    Primitive(&'a dyn PrimitiveRef),
+    Any(&'a DynamicObject),
+    Null,
+}
+
+pub trait ObjectRef {
+    /// Fields which have value, in the declaration order. Absent optional fields are skipped.
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)>;
+
+    /// Untagged unions holding a non-object variant return it here, to be used in place of the
+    /// object.
+    fn variant(&self) -> Option<FieldRefReflection> {
+        None
+    }
+}
+
+pub trait ListRef {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, index: usize) -> Option<FieldRefReflection>;
+}
+
+impl<'a> dyn ListRef + 'a {
+    pub fn iter(&self) -> ListRefIter {
+        ListRefIter {
+            list: self,
+            index: 0,
+        }
+    }
+}
+
+pub struct ListRefIter<'a> {
+    list: &'a dyn ListRef,
+    index: usize,
+}
+
+impl<'a> Iterator for ListRefIter<'a> {
+    type Item = FieldRefReflection<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.list.get(self.index)?;
+        self.index += 1;
+        Some(item)
+    }
+}
+
+pub trait PrimitiveRef {
+    fn value(&self) -> PrimitiveValueRef;
+}
+
 // Blanket implementations
 
 impl<T> Object for HashMap<String, T>
@@ -69,6 +413,17 @@ where
         let child = self.entry(field_name.to_owned()).or_insert_with(T::default);
         Ok(FieldMutReflection::Object(child))
     }
+
+    fn discard(&mut self, field_name: &str) {
+        self.remove(field_name);
+    }
+
+    fn set_null(&mut self, field_name: &str) -> Result<(), ReflectionError> {
+        let mut child = T::default();
+        child.make_null()?;
+        self.insert(field_name.to_owned(), child);
+        Ok(())
+    }
 }
 
 impl<T> Object for HashMap<String, Vec<T>>
@@ -79,6 +434,10 @@ where
         let list = self.entry(field_name.to_owned()).or_insert_with(Vec::new);
         Ok(FieldMutReflection::List(list))
     }
+
+    fn discard(&mut self, field_name: &str) {
+        self.remove(field_name);
+    }
 }
 
 impl<T> List for Vec<T>
@@ -89,30 +448,133 @@ where
         self.push(T::default());
         FieldMutReflection::Object(self.last_mut().unwrap())
     }
+
+    fn push_null(&mut self) -> Result<(), ReflectionError> {
+        let mut item = T::default();
+        item.make_null()?;
+        self.push(item);
+        Ok(())
+    }
+
+    fn discard_last(&mut self) {
+        self.pop();
+    }
 }
 
-// Any (serde_json::Value) support
+impl<T> ObjectFields for HashMap<String, T> {}
 
-// serde_json::Value
+impl<T> ObjectRef for HashMap<String, T>
+where
+    T: ObjectRef,
+{
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+        self.iter()
+            .map(|(name, value)| (name.as_str(), FieldRefReflection::Object(value)))
+            .collect()
+    }
+}
 
-impl Object for HashMap<String, serde_json::Value> {
-    fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
-        let child = self
-            .entry(field_name.to_owned())
-            .or_insert(serde_json::Value::Null);
-        Ok(FieldMutReflection::Any(child))
+impl<T> ObjectRef for HashMap<String, Vec<T>>
+where
+    Vec<T>: ListRef,
+{
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+        self.iter()
+            .map(|(name, list)| (name.as_str(), FieldRefReflection::List(list)))
+            .collect()
     }
 }
 
-impl List for Vec<serde_json::Value> {
-    fn push(&mut self) -> FieldMutReflection {
-        self.push(serde_json::Value::Null);
-        FieldMutReflection::Any(self.last_mut().unwrap())
+impl<T> ListRef for Vec<T>
+where
+    T: ObjectRef,
+{
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<FieldRefReflection> {
+        <[T]>::get(self, index).map(|item| FieldRefReflection::Object(item))
     }
 }
 
 // Primitives support
 
+/// Conversion from the primitive value into the concrete primitive type.
+trait FromPrimitiveValue: Sized {
+    fn from_value(value: PrimitiveValue) -> Result<Self, ReflectionError>;
+}
+
+impl FromPrimitiveValue for String {
+    fn from_value(value: PrimitiveValue) -> Result<Self, ReflectionError> {
+        match value {
+            PrimitiveValue::String(v) => Ok(v),
+            v => Err(ReflectionError::ValueMismatch {
+                expected: PrimitiveValueKind::String,
+                actual: v.kind(),
+            }),
+        }
+    }
+}
+
+impl FromPrimitiveValue for bool {
+    fn from_value(value: PrimitiveValue) -> Result<Self, ReflectionError> {
+        match value {
+            PrimitiveValue::Bool(v) => Ok(v),
+            v => Err(ReflectionError::ValueMismatch {
+                expected: PrimitiveValueKind::Bool,
+                actual: v.kind(),
+            }),
+        }
+    }
+}
+
+impl FromPrimitiveValue for f64 {
+    fn from_value(value: PrimitiveValue) -> Result<Self, ReflectionError> {
+        match value {
+            PrimitiveValue::F64(v) => Ok(v),
+            PrimitiveValue::I64(v) => Ok(v as f64),
+            PrimitiveValue::U64(v) => Ok(v as f64),
+            v => Err(ReflectionError::ValueMismatch {
+                expected: PrimitiveValueKind::F64,
+                actual: v.kind(),
+            }),
+        }
+    }
+}
+
+macro_rules! integer {
+    ($typ:ty => $kind:ident, $wide:ident) => {
+        impl FromPrimitiveValue for $typ {
+            fn from_value(value: PrimitiveValue) -> Result<Self, ReflectionError> {
+                let out_of_range = |value: &dyn std::fmt::Display| ReflectionError::OutOfRange {
+                    expected: stringify!($typ),
+                    value: value.to_string(),
+                };
+                match value {
+                    PrimitiveValue::I64(v) => <$typ>::try_from(v).map_err(|_| out_of_range(&v)),
+                    PrimitiveValue::U64(v) => <$typ>::try_from(v).map_err(|_| out_of_range(&v)),
+                    v => Err(ReflectionError::ValueMismatch {
+                        expected: PrimitiveValueKind::$kind,
+                        actual: v.kind(),
+                    }),
+                }
+            }
+        }
+
+        impl<'a> From<&'a $typ> for PrimitiveValueRef<'a> {
+            fn from(value: &'a $typ) -> Self {
+                PrimitiveValueRef::$kind($wide::from(*value))
+            }
+        }
+    };
+}
+
+integer!(i64 => I64, i64);
+integer!(i32 => I64, i64);
+integer!(u64 => U64, u64);
+integer!(u32 => U64, u64);
+
 macro_rules! primitive {
     ($typ:ty => $kind:ident) => {
         impl Primitive for $typ {
@@ -121,16 +583,8 @@ macro_rules! primitive {
             }
 
             fn set(&mut self, value: PrimitiveValue) -> Result<(), ReflectionError> {
-                match value {
-                    PrimitiveValue::$kind(s) => {
-                        *self = s;
-                        Ok(())
-                    }
-                    v => Err(ReflectionError::ValueMismatch {
-                        expected: PrimitiveValueKind::$kind,
-                        actual: v.kind(),
-                    }),
-                }
+                *self = <$typ>::from_value(value)?;
+                Ok(())
             }
         }
 
@@ -141,6 +595,10 @@ macro_rules! primitive {
                     .or_insert_with(Default::default);
                 Ok(FieldMutReflection::Primitive(primitive))
             }
+
+            fn discard(&mut self, field_name: &str) {
+                self.remove(field_name);
+            }
         }
 
         impl List for Vec<$typ> {
@@ -148,9 +606,42 @@ macro_rules! primitive {
                 self.push(<$typ>::default());
                 FieldMutReflection::Primitive(self.last_mut().unwrap())
             }
+
+            fn discard_last(&mut self) {
+                self.pop();
+            }
+        }
+
+        impl PrimitiveRef for $typ {
+            fn value(&self) -> PrimitiveValueRef {
+                PrimitiveValueRef::from(self)
+            }
+        }
+
+        impl ObjectRef for HashMap<String, $typ> {
+            fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+                self.iter()
+                    .map(|(name, value)| (name.as_str(), FieldRefReflection::Primitive(value)))
+                    .collect()
+            }
+        }
+
+        impl ListRef for Vec<$typ> {
+            fn len(&self) -> usize {
+                <[$typ]>::len(self)
+            }
+
+            fn get(&self, index: usize) -> Option<FieldRefReflection> {
+                <[$typ]>::get(self, index).map(|item| FieldRefReflection::Primitive(item))
+            }
         }
     };
 }
 
 primitive!(String => String);
 primitive!(bool => Bool);
+primitive!(i64 => I64);
+primitive!(i32 => I64);
+primitive!(u64 => U64);
+primitive!(u32 => U64);
+primitive!(f64 => F64);
@@ -0,0 +1,192 @@
+//! Objects of the types selected at runtime, by the name given in the discriminator field.
+//!
+//! Types are registered in the `TypeRegistry`, which is given to the deserializer in the
+//! `dyndeser::Options`. Fields of type `Discriminated` (or lists and maps of them) are
+//! deserialized by reading the discriminator field first and creating the object of the
+//! registered type; fields which came before the discriminator are replayed into the object.
+use crate::reflection::{
+    DynObject, FieldMutReflection, FieldRefReflection, List, Object, ObjectRef, Polymorphic,
+    ReflectionError,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+type Factory = Box<dyn Fn() -> Box<dyn DynObject> + Send + Sync>;
+
+/// Registry of the types which could be selected by name.
+pub struct TypeRegistry {
+    discriminator: String,
+    factories: HashMap<String, Factory>,
+}
+
+impl TypeRegistry {
+    /// Create an empty registry, with types selected by the given discriminator field.
+    pub fn new(discriminator: &str) -> Self {
+        TypeRegistry {
+            discriminator: discriminator.to_owned(),
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Name of the field holding the type name.
+    pub fn discriminator(&self) -> &str {
+        &self.discriminator
+    }
+
+    /// Register type, created via `Default`.
+    pub fn register<T>(&mut self, type_name: &str) -> &mut Self
+    where
+        T: DynObject + Default,
+    {
+        self.register_with(type_name, || Box::new(T::default()))
+    }
+
+    /// Register type, created by the given factory.
+    pub fn register_with<F>(&mut self, type_name: &str, factory: F) -> &mut Self
+    where
+        F: Fn() -> Box<dyn DynObject> + Send + Sync + 'static,
+    {
+        self.factories
+            .insert(type_name.to_owned(), Box::new(factory));
+        self
+    }
+
+    /// Create object of the given type, if it is registered.
+    pub fn create(&self, type_name: &str) -> Option<Box<dyn DynObject>> {
+        self.factories.get(type_name).map(|factory| factory())
+    }
+
+    /// Names of all registered types, sorted.
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for TypeRegistry {
+    fn default() -> Self {
+        TypeRegistry::new("type")
+    }
+}
+
+impl fmt::Debug for TypeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypeRegistry")
+            .field("discriminator", &self.discriminator)
+            .field("types", &self.type_names())
+            .finish()
+    }
+}
+
+/// Object of the type selected by the discriminator field. Empty until deserialized (or set).
+#[derive(Default)]
+pub struct Discriminated {
+    value: Option<DiscriminatedValue>,
+}
+
+struct DiscriminatedValue {
+    discriminator: String,
+    type_name: String,
+    object: Box<dyn DynObject>,
+}
+
+impl Discriminated {
+    pub fn new(discriminator: &str, type_name: &str, object: Box<dyn DynObject>) -> Self {
+        let mut discriminated = Discriminated::default();
+        discriminated.set_object(discriminator, type_name, object);
+        discriminated
+    }
+
+    pub fn type_name(&self) -> Option<&str> {
+        self.value.as_ref().map(|value| value.type_name.as_str())
+    }
+
+    pub fn object(&self) -> Option<&dyn DynObject> {
+        self.value.as_ref().map(|value| &*value.object)
+    }
+
+    pub fn object_mut(&mut self) -> Option<&mut dyn DynObject> {
+        self.value.as_mut().map(|value| &mut *value.object)
+    }
+
+    pub fn downcast_ref<T: DynObject>(&self) -> Option<&T> {
+        self.object()?.downcast_ref()
+    }
+
+    pub fn downcast_mut<T: DynObject>(&mut self) -> Option<&mut T> {
+        self.object_mut()?.downcast_mut()
+    }
+}
+
+impl fmt::Debug for Discriminated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Discriminated")
+            .field("type_name", &self.type_name())
+            .finish()
+    }
+}
+
+impl Polymorphic for Discriminated {
+    fn set_object(
+        &mut self,
+        discriminator: &str,
+        type_name: &str,
+        object: Box<dyn DynObject>,
+    ) -> &mut dyn Object {
+        let value = DiscriminatedValue {
+            discriminator: discriminator.to_owned(),
+            type_name: type_name.to_owned(),
+            object,
+        };
+        self.value = Some(value);
+        self.value.as_mut().unwrap().object.as_object()
+    }
+}
+
+impl ObjectRef for Discriminated {
+    fn fields(&self) -> Vec<(&str, FieldRefReflection)> {
+        let value = match self.value {
+            Some(ref value) => value,
+            None => return Vec::new(),
+        };
+        let mut fields = vec![(
+            value.discriminator.as_str(),
+            FieldRefReflection::Primitive(&value.type_name),
+        )];
+        // Objects which also have the discriminator field keep it in sync with the type
+        fields.extend(
+            value
+                .object
+                .as_object_ref()
+                .fields()
+                .into_iter()
+                .filter(|(name, _)| *name != value.discriminator),
+        );
+        fields
+    }
+}
+
+impl Object for HashMap<String, Discriminated> {
+    fn create(&mut self, field_name: &str) -> Result<FieldMutReflection, ReflectionError> {
+        let child = self
+            .entry(field_name.to_owned())
+            .or_insert_with(Default::default);
+        Ok(FieldMutReflection::Polymorphic(child))
+    }
+
+    fn discard(&mut self, field_name: &str) {
+        self.remove(field_name);
+    }
+}
+
+impl List for Vec<Discriminated> {
+    fn push(&mut self) -> FieldMutReflection {
+        self.push(Discriminated::default());
+        FieldMutReflection::Polymorphic(self.last_mut().unwrap())
+    }
+
+    fn discard_last(&mut self) {
+        self.pop();
+    }
+}
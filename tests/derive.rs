@@ -0,0 +1,62 @@
+//! Deriving `Object` on types defined outside of the `dynser` crate.
+use dynser_derive::Object;
+
+#[derive(Default, Debug, PartialEq, Object)]
+#[dynser(rename_all = "camelCase")]
+struct Package {
+    #[required]
+    name: String,
+    latest_version: Option<String>,
+    authors: Vec<Author>,
+}
+
+#[derive(Default, Debug, PartialEq, Object)]
+struct Author {
+    name: String,
+    email: Option<String>,
+}
+
+/// Reflection API re-exported by another crate.
+mod reexport {
+    pub use dynser::reflection;
+}
+
+#[derive(Default, Debug, PartialEq, Object)]
+#[dynser(crate = "crate::reexport")]
+struct Mirror {
+    url: String,
+}
+
+#[test]
+fn derive_test() {
+    let input = r#"{
+  "name": "dynser",
+  "latestVersion": "0.1.0",
+  "authors": [{"name": "Ivan Dubrov"}]
+}"#;
+    let mut package = Package::default();
+    dynser::dyndeser::read_json(input, &mut package).unwrap();
+    let expected = Package {
+        name: "dynser".to_owned(),
+        latest_version: Some("0.1.0".to_owned()),
+        authors: vec![Author {
+            name: "Ivan Dubrov".to_owned(),
+            email: None,
+        }],
+    };
+    assert_eq!(expected, package);
+    assert_eq!(
+        r#"{"name":"dynser","latestVersion":"0.1.0","authors":[{"name":"Ivan Dubrov"}]}"#,
+        dynser::dynser::write_json(&package).unwrap()
+    );
+
+    let err = dynser::dyndeser::read_json("{}", &mut package).unwrap_err();
+    assert_eq!("missing field `name` at line 1 column 2", err.to_string());
+}
+
+#[test]
+fn crate_override_test() {
+    let mut mirror = Mirror::default();
+    dynser::dyndeser::read_json(r#"{"url": "https://example.com"}"#, &mut mirror).unwrap();
+    assert_eq!("https://example.com", mirror.url);
+}
@@ -0,0 +1,11 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Info {
+    #[dynser(alias = "name")]
+    title: String,
+    #[dynser(alias = "name")]
+    summary: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: Box<Option<String>>,
+}
+
+fn main() {}
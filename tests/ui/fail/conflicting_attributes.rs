@@ -0,0 +1,10 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    #[primitive]
+    #[any]
+    field: String,
+}
+
+fn main() {}
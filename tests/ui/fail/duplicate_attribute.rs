@@ -0,0 +1,10 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    #[primitive]
+    #[primitive]
+    field: String,
+}
+
+fn main() {}
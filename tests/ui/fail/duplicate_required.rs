@@ -0,0 +1,11 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Info {
+    #[required]
+    #[primitive]
+    #[required]
+    title: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: std::collections::HashMap<String, Option<String>>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Info {
+    title: String,
+    #[dynser(alias = "title")]
+    summary: String,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: Option<Option<String>>,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: Vec<Vec<String>>,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Object)]
+enum Shape {
+    Circle { radius: f64 },
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: (String, String),
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use dynser_derive::Object;
+
+#[derive(Object)]
+#[dynser(rename_all = "lowercase")]
+enum Method {
+    Get,
+    GET,
+}
+
+impl Default for Method {
+    fn default() -> Self {
+        Method::Get
+    }
+}
+
+fn main() {}
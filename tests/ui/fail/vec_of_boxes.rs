@@ -0,0 +1,13 @@
+use dynser_derive::Object;
+
+#[derive(Default, Object)]
+struct Item {
+    name: String,
+}
+
+#[derive(Default, Object)]
+struct Shapes {
+    field: Vec<Box<Item>>,
+}
+
+fn main() {}
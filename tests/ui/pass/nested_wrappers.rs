@@ -0,0 +1,61 @@
+use dynser_derive::Object;
+use std::collections::HashMap;
+
+#[derive(Default, Debug, PartialEq, Object)]
+struct Item {
+    name: String,
+}
+
+#[derive(Default, Debug, PartialEq, Object)]
+struct Shapes {
+    tags: Option<Vec<String>>,
+    items: std::vec::Vec<Item>,
+    index: Option<HashMap<String, Item>>,
+    groups: std::collections::HashMap<String, Vec<String>>,
+    boxed: Box<Item>,
+    optional_boxed: Option<Box<Item>>,
+    boxed_list: Box<Vec<Item>>,
+    title: std::string::String,
+    flag: Option<bool>,
+}
+
+fn main() {
+    let input = r#"{
+  "tags": ["a", "b"],
+  "items": [{"name": "first"}],
+  "index": {"key": {"name": "second"}},
+  "groups": {"group": ["c"]},
+  "boxed": {"name": "third"},
+  "optional_boxed": {"name": "fourth"},
+  "boxed_list": [{"name": "fifth"}],
+  "title": "Shapes",
+  "flag": true
+}"#;
+    let mut shapes = Shapes::default();
+    dynser::dyndeser::read_json(input, &mut shapes).unwrap();
+
+    let item = |name: &str| Item {
+        name: name.to_owned(),
+    };
+    let mut index = HashMap::new();
+    index.insert("key".to_owned(), item("second"));
+    let mut groups = HashMap::new();
+    groups.insert("group".to_owned(), vec!["c".to_owned()]);
+    let expected = Shapes {
+        tags: Some(vec!["a".to_owned(), "b".to_owned()]),
+        items: vec![item("first")],
+        index: Some(index),
+        groups,
+        boxed: Box::new(item("third")),
+        optional_boxed: Some(Box::new(item("fourth"))),
+        boxed_list: Box::new(vec![item("fifth")]),
+        title: "Shapes".to_owned(),
+        flag: Some(true),
+    };
+    assert_eq!(expected, shapes);
+
+    let output = dynser::dynser::write_json(&shapes).unwrap();
+    let mut copy = Shapes::default();
+    dynser::dyndeser::read_json(&output, &mut copy).unwrap();
+    assert_eq!(expected, copy);
+}